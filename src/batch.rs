@@ -0,0 +1,243 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::{
+    report::{junit_xml, markdown_cell, write_report, RunReport},
+    state::TokenUsage,
+};
+
+/// A task queued in a batch file
+///
+/// Entries are either a bare task string or an object overriding the
+/// iteration limit, inactivity timeout and verification commands for that
+/// task.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BatchTask {
+    /// Task description for the worker
+    pub task: String,
+    /// Maximum iterations for this task
+    #[serde(default)]
+    pub max_iterations: Option<usize>,
+    /// Inactivity timeout in seconds for this task
+    #[serde(default)]
+    pub inactivity_timeout: Option<u64>,
+    /// Verification commands for this task, replacing the configured ones
+    #[serde(default)]
+    pub verify: Option<Vec<String>>,
+}
+
+/// Batch file entry, a bare string or a full task
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TaskEntry {
+    Task(String),
+    Spec(BatchTask),
+}
+
+impl From<TaskEntry> for BatchTask {
+    fn from(entry: TaskEntry) -> Self {
+        match entry {
+            TaskEntry::Task(task) => BatchTask {
+                task,
+                max_iterations: None,
+                inactivity_timeout: None,
+                verify: None,
+            },
+            TaskEntry::Spec(task) => task,
+        }
+    }
+}
+
+/// YAML batch file, either a list of tasks or a `tasks:` key holding one
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum YamlBatch {
+    List(Vec<TaskEntry>),
+    Tasks { tasks: Vec<TaskEntry> },
+}
+
+/// Load tasks from a `.jsonl` / `.ndjson` file, or YAML otherwise
+pub fn load_tasks(path: &Path) -> Result<Vec<BatchTask>> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read batch file {}", path.display()))?;
+
+    let is_jsonl = matches!(
+        path.extension().and_then(|ext| ext.to_str()),
+        Some("jsonl") | Some("ndjson")
+    );
+    let tasks = if is_jsonl {
+        parse_jsonl(&contents)
+    } else {
+        parse_yaml(&contents)
+    }
+    .with_context(|| format!("Invalid batch file {}", path.display()))?;
+
+    if tasks.is_empty() {
+        anyhow::bail!("Batch file {} contains no tasks", path.display());
+    }
+    Ok(tasks)
+}
+
+/// Parse a YAML list of tasks
+pub fn parse_yaml(contents: &str) -> Result<Vec<BatchTask>> {
+    let entries = match serde_norway::from_str(contents)? {
+        YamlBatch::List(entries) | YamlBatch::Tasks { tasks: entries } => entries,
+    };
+    validate(entries.into_iter().map(BatchTask::from).collect())
+}
+
+/// Parse one JSON task per line, skipping blank lines
+pub fn parse_jsonl(contents: &str) -> Result<Vec<BatchTask>> {
+    let tasks = contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            serde_json::from_str::<TaskEntry>(line)
+                .map(BatchTask::from)
+                .with_context(|| format!("Invalid task on line {}", index + 1))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    validate(tasks)
+}
+
+fn validate(tasks: Vec<BatchTask>) -> Result<Vec<BatchTask>> {
+    if let Some(index) = tasks.iter().position(|t| t.task.trim().is_empty()) {
+        anyhow::bail!("Task {} is empty", index + 1);
+    }
+    Ok(tasks)
+}
+
+/// A batch task that failed before producing a run report
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchError {
+    /// Task description
+    pub task: String,
+    /// Error message
+    pub error: String,
+}
+
+/// Aggregate report over every task in a batch
+#[derive(Clone, Serialize, Deserialize)]
+pub struct BatchReport {
+    /// Number of tasks in the batch file
+    pub total: usize,
+    pub completed: usize,
+    pub aborted: usize,
+    pub max_iterations: usize,
+    pub interrupted: usize,
+    /// Tasks that failed with an error
+    pub errored: usize,
+    /// Tasks not started because the batch was interrupted
+    pub skipped: usize,
+    /// Sum of task runtimes in seconds
+    pub runtime_secs: i64,
+    /// Worker token and cost totals across all tasks
+    pub usage: TokenUsage,
+    /// Report for each task that ran
+    pub runs: Vec<RunReport>,
+    /// Tasks that failed with an error
+    pub errors: Vec<BatchError>,
+}
+
+impl BatchReport {
+    /// Aggregate the reports of a batch of `total` tasks
+    pub fn new(total: usize, runs: Vec<RunReport>, errors: Vec<BatchError>) -> Self {
+        let count = |result: &str| runs.iter().filter(|r| r.result == result).count();
+        let mut usage = TokenUsage::default();
+        for run in &runs {
+            usage += run.usage;
+        }
+
+        Self {
+            total,
+            completed: count("completed"),
+            aborted: count("aborted"),
+            max_iterations: count("max_iterations"),
+            interrupted: count("interrupted"),
+            errored: errors.len(),
+            skipped: total.saturating_sub(runs.len() + errors.len()),
+            runtime_secs: runs.iter().map(|r| r.runtime_secs).sum(),
+            usage,
+            runs,
+            errors,
+        }
+    }
+
+    /// Whether every task completed
+    pub fn all_completed(&self) -> bool {
+        self.completed == self.total
+    }
+
+    /// One-line summary of the counts
+    pub fn format_summary(&self) -> String {
+        let mut summary = format!(
+            "{} tasks: {} completed, {} aborted, {} max iterations",
+            self.total, self.completed, self.aborted, self.max_iterations
+        );
+        if self.interrupted > 0 {
+            summary.push_str(&format!(", {} interrupted", self.interrupted));
+        }
+        if self.errored > 0 {
+            summary.push_str(&format!(", {} errored", self.errored));
+        }
+        if self.skipped > 0 {
+            summary.push_str(&format!(", {} skipped", self.skipped));
+        }
+        summary
+    }
+
+    /// Render the report as pretty-printed JSON
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self).context("Failed to serialize batch report")
+    }
+
+    /// Write the report as JSON to `path`
+    pub fn write_json(&self, path: &Path) -> Result<()> {
+        write_report(path, &self.to_json()?)
+    }
+
+    /// Render the report as a Markdown summary with a row per task
+    pub fn to_markdown(&self) -> String {
+        let mut out = format!("## OpenCode Runner batch: {}\n\n", self.format_summary());
+        out.push_str(&format!(
+            "Runtime: {}s | Tokens: {} in / {} out | Cost: ${:.4}\n\n",
+            self.runtime_secs, self.usage.input, self.usage.output, self.usage.cost
+        ));
+
+        out.push_str("| # | Task | Result | Iterations | Runtime | Reason |\n");
+        out.push_str("|---|---|---|---|---|---|\n");
+        for (index, run) in self.runs.iter().enumerate() {
+            out.push_str(&format!(
+                "| {} | {} | {} | {} | {}s | {} |\n",
+                index + 1,
+                markdown_cell(&run.task),
+                run.result,
+                run.iterations.len(),
+                run.runtime_secs,
+                markdown_cell(run.reason.as_deref().unwrap_or(""))
+            ));
+        }
+        for error in &self.errors {
+            out.push_str(&format!(
+                "| - | {} | error | - | - | {} |\n",
+                markdown_cell(&error.task),
+                markdown_cell(&error.error)
+            ));
+        }
+
+        out
+    }
+
+    /// Write the report as Markdown to `path`
+    pub fn write_markdown(&self, path: &Path) -> Result<()> {
+        write_report(path, &self.to_markdown())
+    }
+
+    /// Write the task runs and errors as a JUnit XML test suite to `path`
+    pub fn write_junit(&self, path: &Path) -> Result<()> {
+        write_report(path, &junit_xml(&self.runs, &self.errors))
+    }
+}
@@ -1,6 +1,9 @@
 use anyhow::{Context, Result};
+use std::time::Duration;
 use tracing::{debug, info, warn};
 
+use crate::sampler::{tool_detail, tool_line};
+
 // Platform-specific imports
 use opencode_rs::{
     sse::SseSubscription,
@@ -12,10 +15,84 @@ use opencode_rs::{
     Client as OpencodeClient,
 };
 
+/// Attempts made to (re)subscribe to session events before giving up
+const SUBSCRIBE_ATTEMPTS: u32 = 5;
+/// Upper bound for the delay between subscription attempts
+const MAX_SUBSCRIBE_DELAY: Duration = Duration::from_secs(8);
+
 pub struct OpenCodeClient {
     inner: OpencodeClient,
 }
 
+/// Text and tool calls recovered from a session's message history
+#[derive(Debug, Clone, Default)]
+pub struct SessionHistory {
+    /// Text of the first user message, usually the task
+    pub first_prompt: Option<String>,
+    /// Lines in the same format the sampler produces
+    pub lines: Vec<String>,
+    /// Whether the last message is an assistant reply still being written
+    pub busy: bool,
+}
+
+impl SessionHistory {
+    /// Build from the JSON of a session's messages
+    /// (`[{"info": {"role": ...}, "parts": [...]}, ...]`)
+    pub fn from_messages(messages: &serde_json::Value) -> Self {
+        let mut history = Self::default();
+
+        for message in messages.as_array().into_iter().flatten() {
+            let role = message
+                .pointer("/info/role")
+                .and_then(|v| v.as_str())
+                .unwrap_or("");
+            let parts = message.get("parts").and_then(|v| v.as_array());
+
+            for part in parts.into_iter().flatten() {
+                match part.get("type").and_then(|v| v.as_str()) {
+                    Some("text") => {
+                        let Some(text) = part.get("text").and_then(|v| v.as_str()) else {
+                            continue;
+                        };
+                        if role == "user" && history.first_prompt.is_none() {
+                            history.first_prompt = Some(text.to_string());
+                        }
+                        history.lines.extend(
+                            text.lines()
+                                .map(|line| line.trim())
+                                .filter(|line| !line.is_empty())
+                                .map(|line| line.to_string()),
+                        );
+                    }
+                    Some("tool") => {
+                        let tool = part
+                            .get("tool")
+                            .and_then(|v| v.as_str())
+                            .unwrap_or("unknown");
+                        history
+                            .lines
+                            .push(tool_line(tool, tool_detail(part).as_deref()));
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        history.busy = messages
+            .as_array()
+            .and_then(|messages| messages.last())
+            .is_some_and(|last| {
+                last.pointer("/info/role").and_then(|v| v.as_str()) == Some("assistant")
+                    && last
+                        .pointer("/info/time/completed")
+                        .filter(|v| !v.is_null())
+                        .is_none()
+            });
+
+        history
+    }
+}
+
 impl OpenCodeClient {
     /// Create a new client connected to the OpenCode server
     pub async fn connect(base_url: &str) -> Result<Self> {
@@ -37,6 +114,18 @@ impl OpenCodeClient {
         Ok(Self { inner: client })
     }
 
+    /// Check that the server answers its health endpoint
+    pub async fn health_check(&self) -> Result<()> {
+        let health = self
+            .inner
+            .misc()
+            .health()
+            .await
+            .context("OpenCode server health check failed")?;
+        debug!("OpenCode server health: {:?}", health);
+        Ok(())
+    }
+
     /// Create a new session with an initial task
     pub async fn create_session(&self, task: &str) -> Result<String> {
         info!("Creating new session with task");
@@ -84,20 +173,58 @@ impl OpenCodeClient {
         Ok(session.id)
     }
 
-    /// Subscribe to session events (SSE stream)
-    pub async fn subscribe(&self, session_id: &str) -> Result<SseSubscription> {
-        debug!("Subscribing to session events for {}", session_id);
+    /// Fetch the messages already in a session
+    pub async fn session_history(&self, session_id: &str) -> Result<SessionHistory> {
+        debug!("Fetching message history for session {}", session_id);
 
-        let subscription = self
+        let messages = self
             .inner
-            .subscribe_session(session_id)
+            .messages()
+            .list(session_id)
             .await
-            .context("Failed to subscribe to session events")?;
+            .context("Failed to list session messages")?;
+
+        let messages =
+            serde_json::to_value(&messages).context("Failed to read session messages")?;
 
-        Ok(subscription)
+        Ok(SessionHistory::from_messages(&messages))
     }
 
-    /// Send a message to the session (for future feedback feature)
+    /// Subscribe to session events (SSE stream), retrying with exponential backoff
+    pub async fn subscribe(&self, session_id: &str) -> Result<SseSubscription> {
+        let mut delay = Duration::from_millis(500);
+        let mut attempt = 1;
+
+        loop {
+            debug!(
+                "Subscribing to session events for {} (attempt {})",
+                session_id, attempt
+            );
+
+            match self.inner.subscribe_session(session_id).await {
+                Ok(subscription) => return Ok(subscription),
+                Err(e) if attempt < SUBSCRIBE_ATTEMPTS => {
+                    warn!(
+                        "Failed to subscribe to session events (attempt {}): {}, retrying in {:?}",
+                        attempt, e, delay
+                    );
+                    tokio::time::sleep(delay).await;
+                    delay = (delay * 2).min(MAX_SUBSCRIBE_DELAY);
+                    attempt += 1;
+                }
+                Err(e) => {
+                    return Err(e).with_context(|| {
+                        format!(
+                            "Failed to subscribe to session events after {} attempts",
+                            attempt
+                        )
+                    });
+                }
+            }
+        }
+    }
+
+    /// Send a follow-up message to the session (used for reviewer guidance)
     pub async fn send_message(&self, session_id: &str, text: &str) -> Result<()> {
         debug!("Sending message to session {}: {}", session_id, text);
 
@@ -125,6 +252,19 @@ impl OpenCodeClient {
         Ok(())
     }
 
+    /// Abort whatever the session is currently running
+    pub async fn abort_session(&self, session_id: &str) -> Result<()> {
+        debug!("Aborting session {}", session_id);
+
+        self.inner
+            .sessions()
+            .abort(session_id)
+            .await
+            .context("Failed to abort session")?;
+
+        Ok(())
+    }
+
     /// Get the inner client (for advanced usage)
     pub fn inner(&self) -> &OpencodeClient {
         &self.inner
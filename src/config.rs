@@ -1,8 +1,14 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
+use crate::environment::load_config_from_env;
+use crate::reviewer::{ReviewerBackend, ReviewerOptions};
+
 /// Configuration for the control loop
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ControlConfig {
     /// The task description
     pub task: String,
@@ -41,24 +47,412 @@ impl ControlConfig {
             tokio::time::Duration::from_secs(inactivity_timeout),
         ))
     }
+}
 
-    /// Create ControlConfig from environment variables
-    pub fn from_env() -> Result<Self> {
-        // Check for environment variable overrides
-        let task = std::env::var("OPCODE_TASK").unwrap_or_else(|_| "".to_string());
-        let max_iterations = std::env::var("OPCODE_MAX_ITERATIONS")
-            .unwrap_or_else(|_| "10".to_string())
-            .parse()
-            .unwrap_or(10);
-        let inactivity_timeout = std::env::var("OPCODE_INACTIVITY_TIMEOUT")
-            .unwrap_or_else(|_| "30".to_string())
-            .parse()
-            .unwrap_or(30);
+/// Project-local config file name
+pub const CONFIG_FILE_NAME: &str = "opencode_runner.toml";
 
-        Ok(Self::new(
-            task,
-            max_iterations,
-            tokio::time::Duration::from_secs(inactivity_timeout),
-        ))
+/// One layer of runner settings (a config file profile, the environment or
+/// the command line); unset fields fall through to the layer below
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigLayer {
+    pub task: Option<String>,
+    pub working_dir: Option<PathBuf>,
+    pub worker_model: Option<String>,
+    pub reviewer_backend: Option<ReviewerBackend>,
+    pub reviewer_url: Option<String>,
+    pub reviewer_model: Option<String>,
+    pub reviewer_system_prompt: Option<String>,
+    /// File holding the reviewer system prompt, overriding `reviewer_system_prompt`
+    pub reviewer_system_prompt_file: Option<PathBuf>,
+    /// Review prompt template file
+    pub reviewer_prompt_template: Option<PathBuf>,
+    /// Reviewer prompt size limit in estimated tokens
+    pub reviewer_token_budget: Option<usize>,
+    pub max_iterations: Option<usize>,
+    /// Inactivity timeout in seconds
+    pub inactivity_timeout: Option<u64>,
+    pub sample_lines: Option<usize>,
+    pub headless: Option<bool>,
+    pub server_url: Option<String>,
+    pub runs_dir: Option<PathBuf>,
+    /// Run each task in its own git worktree
+    pub worktree: Option<bool>,
+    pub worktrees_dir: Option<PathBuf>,
+    /// Checkpoint the working tree after every review
+    pub checkpoints: Option<bool>,
+    /// Record session events to the run directory for replay
+    pub record_events: Option<bool>,
+    /// Commands that must pass before a run counts as completed
+    pub verify: Option<Vec<String>>,
+    /// Verification command timeout in seconds
+    pub verify_timeout: Option<u64>,
+    /// Number of batch tasks run concurrently
+    pub jobs: Option<usize>,
+    pub reviewer_max_concurrent: Option<usize>,
+    pub reviewer_min_interval_ms: Option<u64>,
+}
+
+/// Contents of an `opencode_runner.toml` file
+///
+/// ```toml
+/// default_profile = "local"
+///
+/// [defaults]
+/// max_iterations = 20
+///
+/// [profiles.local]
+/// reviewer_url = "http://localhost:11434/v1"
+/// reviewer_model = "qwen2.5-coder"
+///
+/// [token_budgets]
+/// "qwen2.5-coder" = 6000
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigFile {
+    /// Profile used when none is selected on the command line or environment
+    pub default_profile: Option<String>,
+    /// Settings shared by every profile
+    #[serde(default)]
+    pub defaults: ConfigLayer,
+    /// Named profiles
+    #[serde(default)]
+    pub profiles: HashMap<String, ConfigLayer>,
+    /// Reviewer prompt token budgets by reviewer model, used when no
+    /// layer sets `reviewer_token_budget`
+    #[serde(default)]
+    pub token_budgets: HashMap<String, usize>,
+}
+
+impl ConfigFile {
+    /// Parse a config file from TOML
+    pub fn parse(contents: &str) -> Result<Self> {
+        toml::from_str(contents).context("Invalid config file")
+    }
+
+    /// Read a config file, returning `None` if it does not exist
+    pub fn read(path: &Path) -> Result<Option<Self>> {
+        if !path.is_file() {
+            return Ok(None);
+        }
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Self::parse(&contents)
+            .with_context(|| format!("Failed to load {}", path.display()))
+            .map(Some)
+    }
+
+    /// Settings for `profile` layered over the file's defaults
+    pub fn resolve(&self, profile: Option<&str>) -> ConfigLayer {
+        let mut layer = self.defaults.clone();
+        if let Some(settings) = profile.and_then(|name| self.profiles.get(name)) {
+            layer.merge(settings);
+        }
+        layer
+    }
+}
+
+impl ConfigLayer {
+    /// Overwrite fields with those set in `other`
+    pub fn merge(&mut self, other: &ConfigLayer) {
+        fn set<T: Clone>(field: &mut Option<T>, value: &Option<T>) {
+            if value.is_some() {
+                field.clone_from(value);
+            }
+        }
+
+        set(&mut self.task, &other.task);
+        set(&mut self.working_dir, &other.working_dir);
+        set(&mut self.worker_model, &other.worker_model);
+        set(&mut self.reviewer_backend, &other.reviewer_backend);
+        set(&mut self.reviewer_url, &other.reviewer_url);
+        set(&mut self.reviewer_model, &other.reviewer_model);
+        set(
+            &mut self.reviewer_system_prompt,
+            &other.reviewer_system_prompt,
+        );
+        set(
+            &mut self.reviewer_system_prompt_file,
+            &other.reviewer_system_prompt_file,
+        );
+        set(
+            &mut self.reviewer_prompt_template,
+            &other.reviewer_prompt_template,
+        );
+        set(
+            &mut self.reviewer_token_budget,
+            &other.reviewer_token_budget,
+        );
+        set(&mut self.max_iterations, &other.max_iterations);
+        set(&mut self.inactivity_timeout, &other.inactivity_timeout);
+        set(&mut self.sample_lines, &other.sample_lines);
+        set(&mut self.headless, &other.headless);
+        set(&mut self.server_url, &other.server_url);
+        set(&mut self.runs_dir, &other.runs_dir);
+        set(&mut self.worktree, &other.worktree);
+        set(&mut self.worktrees_dir, &other.worktrees_dir);
+        set(&mut self.checkpoints, &other.checkpoints);
+        set(&mut self.record_events, &other.record_events);
+        set(&mut self.verify, &other.verify);
+        set(&mut self.verify_timeout, &other.verify_timeout);
+        set(&mut self.jobs, &other.jobs);
+        set(
+            &mut self.reviewer_max_concurrent,
+            &other.reviewer_max_concurrent,
+        );
+        set(
+            &mut self.reviewer_min_interval_ms,
+            &other.reviewer_min_interval_ms,
+        );
+    }
+}
+
+/// Fully resolved runner settings
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunnerConfig {
+    pub task: Option<String>,
+    pub working_dir: PathBuf,
+    pub worker_model: String,
+    pub reviewer_backend: ReviewerBackend,
+    pub reviewer_url: String,
+    pub reviewer_model: String,
+    pub reviewer_system_prompt: Option<String>,
+    /// File holding the reviewer system prompt, overriding `reviewer_system_prompt`
+    pub reviewer_system_prompt_file: Option<PathBuf>,
+    /// Review prompt template file, see `PromptTemplate`
+    pub reviewer_prompt_template: Option<PathBuf>,
+    /// Reviewer prompt size limit in estimated tokens, unlimited if `None`
+    pub reviewer_token_budget: Option<usize>,
+    pub max_iterations: usize,
+    /// Inactivity timeout in seconds
+    pub inactivity_timeout: u64,
+    pub sample_lines: usize,
+    pub headless: bool,
+    pub server_url: Option<String>,
+    pub runs_dir: PathBuf,
+    /// Run each task in its own git worktree
+    pub worktree: bool,
+    /// Where run worktrees are created
+    pub worktrees_dir: PathBuf,
+    /// Checkpoint the working tree after every review, rolling back on abort
+    pub checkpoints: bool,
+    /// Record session events and reviews to `events.jsonl` in the run directory
+    pub record_events: bool,
+    /// Commands run in the working directory when the worker appears done
+    pub verify: Vec<String>,
+    /// Seconds a verification command may run before it counts as failed
+    pub verify_timeout: u64,
+    /// Number of batch tasks run concurrently
+    pub jobs: usize,
+    /// Reviews in flight at once across concurrent runs
+    pub reviewer_max_concurrent: usize,
+    /// Minimum time between reviewer requests across concurrent runs
+    pub reviewer_min_interval_ms: u64,
+}
+
+impl Default for RunnerConfig {
+    fn default() -> Self {
+        Self {
+            task: None,
+            working_dir: PathBuf::from("."),
+            worker_model: "ollama/llama3.1".to_string(),
+            reviewer_backend: ReviewerBackend::OpenAi,
+            reviewer_url: "http://localhost:11434/v1".to_string(),
+            reviewer_model: "ollama/llama3.1".to_string(),
+            reviewer_system_prompt: None,
+            reviewer_system_prompt_file: None,
+            reviewer_prompt_template: None,
+            reviewer_token_budget: None,
+            max_iterations: 10,
+            inactivity_timeout: 30,
+            sample_lines: 100,
+            headless: false,
+            server_url: None,
+            runs_dir: PathBuf::from(".opencode_runner/runs"),
+            worktree: false,
+            worktrees_dir: PathBuf::from(".opencode_runner/worktrees"),
+            checkpoints: false,
+            record_events: false,
+            verify: Vec::new(),
+            verify_timeout: 600,
+            jobs: 1,
+            reviewer_max_concurrent: 1,
+            reviewer_min_interval_ms: 0,
+        }
+    }
+}
+
+impl RunnerConfig {
+    /// Load settings layered as user file < project file < environment < CLI
+    ///
+    /// `config_path` replaces the project-local `opencode_runner.toml`.
+    /// The profile is taken from the CLI, then `OPCODE_PROFILE`, then the
+    /// files' `default_profile`. Reviewer prompt files are read to check
+    /// they are valid.
+    pub fn load(
+        config_path: Option<&Path>,
+        profile: Option<&str>,
+        cli: &ConfigLayer,
+    ) -> Result<Self> {
+        let mut files = Vec::new();
+        if let Some(path) = user_config_path() {
+            files.extend(ConfigFile::read(&path)?);
+        }
+        match config_path {
+            Some(path) => files.push(
+                ConfigFile::read(path)?
+                    .with_context(|| format!("Config file {} not found", path.display()))?,
+            ),
+            None => files.extend(ConfigFile::read(Path::new(CONFIG_FILE_NAME))?),
+        }
+
+        let env_profile = std::env::var("OPCODE_PROFILE").ok();
+        let default_profile = files.iter().rev().find_map(|f| f.default_profile.clone());
+        let profile = profile
+            .map(|p| p.to_string())
+            .or(env_profile)
+            .or(default_profile);
+
+        let env = load_config_from_env()?;
+        let config = Self::from_layers(&files, profile.as_deref(), &env, cli)?;
+
+        // Catch broken prompt files before any run starts
+        config.reviewer_options()?;
+        Ok(config)
+    }
+
+    /// Resolve settings from already-loaded layers
+    pub fn from_layers(
+        files: &[ConfigFile],
+        profile: Option<&str>,
+        env: &ConfigLayer,
+        cli: &ConfigLayer,
+    ) -> Result<Self> {
+        if let Some(name) = profile {
+            if !files.iter().any(|f| f.profiles.contains_key(name)) {
+                anyhow::bail!("Unknown config profile '{}'", name);
+            }
+        }
+
+        let mut layer = ConfigLayer::default();
+        for file in files {
+            layer.merge(&file.resolve(profile));
+        }
+        layer.merge(env);
+        layer.merge(cli);
+
+        let mut config = Self::default();
+        config.apply(layer);
+        if config.reviewer_token_budget.is_none() {
+            config.reviewer_token_budget = files
+                .iter()
+                .rev()
+                .find_map(|f| f.token_budgets.get(&config.reviewer_model).copied());
+        }
+        Ok(config)
+    }
+
+    /// Reviewer options, reading the prompt files
+    pub fn reviewer_options(&self) -> Result<ReviewerOptions> {
+        ReviewerOptions::load(
+            self.reviewer_system_prompt.as_deref(),
+            self.reviewer_system_prompt_file.as_deref(),
+            self.reviewer_prompt_template.as_deref(),
+            self.reviewer_token_budget,
+        )
     }
+
+    /// Overwrite settings with those set in `layer`
+    pub fn apply(&mut self, layer: ConfigLayer) {
+        if layer.task.is_some() {
+            self.task = layer.task;
+        }
+        if let Some(working_dir) = layer.working_dir {
+            self.working_dir = working_dir;
+        }
+        if let Some(worker_model) = layer.worker_model {
+            self.worker_model = worker_model;
+        }
+        if let Some(reviewer_backend) = layer.reviewer_backend {
+            self.reviewer_backend = reviewer_backend;
+        }
+        if let Some(reviewer_url) = layer.reviewer_url {
+            self.reviewer_url = reviewer_url;
+        }
+        if let Some(reviewer_model) = layer.reviewer_model {
+            self.reviewer_model = reviewer_model;
+        }
+        if layer.reviewer_system_prompt.is_some() {
+            self.reviewer_system_prompt = layer.reviewer_system_prompt;
+        }
+        if layer.reviewer_system_prompt_file.is_some() {
+            self.reviewer_system_prompt_file = layer.reviewer_system_prompt_file;
+        }
+        if layer.reviewer_prompt_template.is_some() {
+            self.reviewer_prompt_template = layer.reviewer_prompt_template;
+        }
+        if layer.reviewer_token_budget.is_some() {
+            self.reviewer_token_budget = layer.reviewer_token_budget;
+        }
+        if let Some(max_iterations) = layer.max_iterations {
+            self.max_iterations = max_iterations;
+        }
+        if let Some(inactivity_timeout) = layer.inactivity_timeout {
+            self.inactivity_timeout = inactivity_timeout;
+        }
+        if let Some(sample_lines) = layer.sample_lines {
+            self.sample_lines = sample_lines;
+        }
+        if let Some(headless) = layer.headless {
+            self.headless = headless;
+        }
+        if layer.server_url.is_some() {
+            self.server_url = layer.server_url;
+        }
+        if let Some(runs_dir) = layer.runs_dir {
+            self.runs_dir = runs_dir;
+        }
+        if let Some(worktree) = layer.worktree {
+            self.worktree = worktree;
+        }
+        if let Some(worktrees_dir) = layer.worktrees_dir {
+            self.worktrees_dir = worktrees_dir;
+        }
+        if let Some(checkpoints) = layer.checkpoints {
+            self.checkpoints = checkpoints;
+        }
+        if let Some(record_events) = layer.record_events {
+            self.record_events = record_events;
+        }
+        if let Some(verify) = layer.verify {
+            self.verify = verify;
+        }
+        if let Some(verify_timeout) = layer.verify_timeout {
+            self.verify_timeout = verify_timeout;
+        }
+        if let Some(jobs) = layer.jobs {
+            self.jobs = jobs;
+        }
+        if let Some(reviewer_max_concurrent) = layer.reviewer_max_concurrent {
+            self.reviewer_max_concurrent = reviewer_max_concurrent;
+        }
+        if let Some(reviewer_min_interval_ms) = layer.reviewer_min_interval_ms {
+            self.reviewer_min_interval_ms = reviewer_min_interval_ms;
+        }
+    }
+
+    /// Control loop configuration for `task`
+    pub fn control_config(&self, task: &str) -> Result<ControlConfig> {
+        ControlConfig::from_args(task, self.max_iterations, self.inactivity_timeout)
+    }
+}
+
+/// Per-user config file (`$XDG_CONFIG_HOME` or `~/.config`)
+pub fn user_config_path() -> Option<PathBuf> {
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_home.join(CONFIG_FILE_NAME))
 }
@@ -1,13 +1,20 @@
 use crate::{
     client::OpenCodeClient,
     config::ControlConfig,
-    reviewer::{ReviewerAction, ReviewerClient, ReviewerContext, ReviewerDecision},
+    git::{Checkpoints, DiffTracker},
+    recording::EventRecorder,
+    reviewer::{Reviewer, ReviewerAction, ReviewerContext, ReviewerDecision},
+    run_store::{RunMeta, RunStore},
     sampler::Sampler,
-    state::State,
+    state::{State, TokenUsage},
+    verify::{self, Verifier},
 };
 use anyhow::{Context, Result};
+use std::fmt;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
 use tracing::{debug, error, info, warn};
 
 use opencode_rs::sse::SseSubscription;
@@ -21,6 +28,32 @@ pub enum RunResult {
     Aborted(String),
     /// Maximum iterations reached
     MaxIterations,
+    /// Run was interrupted by a signal or the user quitting the TUI
+    Interrupted,
+}
+
+impl fmt::Display for RunResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RunResult::Completed => write!(f, "Completed"),
+            RunResult::Aborted(reason) => write!(f, "Aborted: {}", reason),
+            RunResult::MaxIterations => write!(f, "Max iterations reached"),
+            RunResult::Interrupted => write!(f, "Interrupted by user"),
+        }
+    }
+}
+
+/// Why event streaming stopped and a review was triggered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StreamEnd {
+    /// The session went idle after the worker's last message
+    Idle,
+    /// A review-worthy event (error, compaction) arrived
+    Trigger,
+    /// No events arrived within the inactivity timeout
+    Inactive,
+    /// The event stream closed and could not be reopened
+    Closed,
 }
 
 /// Event sent to the TUI
@@ -30,6 +63,8 @@ pub enum UiEvent {
     WorkerOutput(String),
     /// New reviewer decision
     ReviewerDecision(ReviewerDecision),
+    /// Reviewer guidance was sent to the worker session
+    GuidanceSent(String),
     /// Status update
     StatusUpdate(String),
 }
@@ -37,17 +72,33 @@ pub enum UiEvent {
 /// Main control loop orchestrating worker and reviewer
 pub struct ControlLoop {
     client: OpenCodeClient,
-    reviewer: ReviewerClient,
+    reviewer: Arc<dyn Reviewer>,
     sampler: Sampler,
     state: State,
     config: ControlConfig,
+    /// Session to attach to instead of creating one
+    session_id: Option<String>,
+    /// Prompt the attached session to carry on if it is idle
+    resume: bool,
+    /// Where to save the run after every iteration
+    run_store: Option<(RunStore, RunMeta)>,
+    /// Cancelled to interrupt the run from outside (e.g. TUI quit or a signal)
+    cancel: CancellationToken,
+    /// Snapshots of the working tree taken after every decision
+    checkpoints: Option<Checkpoints>,
+    /// Commands that must pass before the run counts as completed
+    verifier: Option<Verifier>,
+    /// Working tree changes between reviews, shown to the reviewer
+    diff_tracker: Option<DiffTracker>,
+    /// Where events and reviews are recorded for replay
+    recorder: Option<EventRecorder>,
 }
 
 impl ControlLoop {
     /// Create a new control loop
     pub fn new(
         client: OpenCodeClient,
-        reviewer: ReviewerClient,
+        reviewer: Arc<dyn Reviewer>,
         sampler: Sampler,
         state: State,
         config: ControlConfig,
@@ -58,21 +109,197 @@ impl ControlLoop {
             sampler,
             state,
             config,
+            session_id: None,
+            resume: false,
+            run_store: None,
+            cancel: CancellationToken::new(),
+            checkpoints: None,
+            verifier: None,
+            diff_tracker: None,
+            recorder: None,
         }
     }
 
+    /// Use an existing session instead of creating one for the task
+    pub fn with_session(mut self, session_id: String) -> Self {
+        self.session_id = Some(session_id);
+        self
+    }
+
+    /// Pick up an interrupted run: if the attached session is idle, prompt
+    /// the worker with the task and the last guidance before reviewing
+    pub fn with_resume(mut self) -> Self {
+        self.resume = true;
+        self
+    }
+
+    /// Save the run to `store` after every iteration
+    pub fn with_run_store(mut self, store: RunStore, meta: RunMeta) -> Self {
+        self.run_store = Some((store, meta));
+        self
+    }
+
+    /// Checkpoint the working tree at the start and after every decision,
+    /// and roll back to the last approved checkpoint (or the starting one)
+    /// when the reviewer aborts
+    pub fn with_checkpoints(mut self, checkpoints: Checkpoints) -> Self {
+        self.checkpoints = Some(checkpoints);
+        self
+    }
+
+    /// Run verification commands whenever the worker appears done, sending
+    /// failures back to the session instead of completing
+    pub fn with_verifier(mut self, verifier: Verifier) -> Self {
+        self.verifier = Some(verifier);
+        self
+    }
+
+    /// Show the reviewer what changed in the working tree since the
+    /// previous review
+    pub fn with_diff_tracker(mut self, diff_tracker: DiffTracker) -> Self {
+        self.diff_tracker = Some(diff_tracker);
+        self
+    }
+
+    /// Record every event, history backfill and review for `replay`
+    pub fn with_recorder(mut self, recorder: EventRecorder) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    /// Also interrupt the run when `shutdown` is cancelled, e.g. by a
+    /// process-wide signal handler; cancelling the run's own token leaves
+    /// `shutdown` alone
+    pub fn with_shutdown(mut self, shutdown: &CancellationToken) -> Self {
+        self.cancel = shutdown.child_token();
+        self
+    }
+
+    /// Token that interrupts the run when cancelled
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.cancel.clone()
+    }
+
     /// Run the control loop
+    ///
+    /// Cancelling the token (or the shutdown token, see `with_shutdown`)
+    /// aborts the worker session and records the interruption before
+    /// returning `RunResult::Interrupted`.
     pub async fn run(&mut self, event_sender: Option<mpsc::Sender<UiEvent>>) -> Result<RunResult> {
-        info!("Starting control loop");
+        let cancel = self.cancel.clone();
 
-        // Create a session with the task
-        let session_id = self
-            .client
-            .create_session(&self.config.task)
-            .await
-            .context("Failed to create session")?;
+        let outcome = tokio::select! {
+            result = self.run_session(&event_sender) => Some(result),
+            _ = cancel.cancelled() => {
+                warn!("Run cancelled, interrupting");
+                None
+            }
+        };
+
+        match outcome {
+            Some(result) => result,
+            None => self.interrupt(&event_sender).await,
+        }
+    }
+
+    /// Abort the worker session and record the interruption
+    async fn interrupt(
+        &mut self,
+        event_sender: &Option<mpsc::Sender<UiEvent>>,
+    ) -> Result<RunResult> {
+        if let Some(ref session_id) = self.session_id {
+            info!("Aborting session {}", session_id);
+            if let Err(e) = self.client.abort_session(session_id).await {
+                warn!("Failed to abort session: {}", e);
+            }
+        }
+
+        // Record against a fresh iteration if the current one was already reviewed
+        let current = self.state.current_iteration();
+        let reviewed = self.state.last_iteration().map(|iter| iter.number) == Some(current);
+        if current == 0 || reviewed {
+            self.state.start_iteration();
+        }
+
+        let decision = ReviewerDecision {
+            action: ReviewerAction::Abort,
+            reason: "interrupted by user".to_string(),
+        };
+        self.state
+            .record_decision(self.sampler.line_count(), decision.clone(), 0);
+        self.save_run();
+
+        if let Some(ref sender) = event_sender {
+            let _ = sender.send(UiEvent::ReviewerDecision(decision)).await;
+        }
+
+        Ok(RunResult::Interrupted)
+    }
+
+    /// Create or attach to the session and run iterations until a result
+    async fn run_session(
+        &mut self,
+        event_sender: &Option<mpsc::Sender<UiEvent>>,
+    ) -> Result<RunResult> {
+        info!(
+            "Starting control loop with {} reviewer",
+            self.reviewer.name()
+        );
+
+        // Before the worker touches the tree, so an abort ahead of any
+        // approved checkpoint still has somewhere to roll back to
+        self.baseline_checkpoint().await;
+
+        let session_id = match self.session_id.clone() {
+            Some(session_id) => {
+                info!("Attaching to existing session: {}", session_id);
+
+                // Seed the reviewer with what already happened in the session
+                let busy = match self.client.session_history(&session_id).await {
+                    Ok(history) => {
+                        info!("Loaded {} lines of session history", history.lines.len());
+                        self.record(|recorder| recorder.record_history(&history.lines));
+                        for line in &history.lines {
+                            self.sampler.add_line(line);
+                        }
+                        history.busy
+                    }
+                    Err(e) => {
+                        warn!("Could not load session history: {}", e);
+                        false
+                    }
+                };
+
+                // An interrupted run aborted the worker, which won't start
+                // again on its own
+                if self.resume && !busy {
+                    info!("Session is idle, prompting the worker to continue");
+                    self.client
+                        .send_message(&session_id, &self.resume_prompt())
+                        .await
+                        .context("Failed to prompt resumed session")?;
+                }
 
-        info!("Created session: {}", session_id);
+                session_id
+            }
+            None => {
+                // Create a session with the task
+                let session_id = self
+                    .client
+                    .create_session(&self.config.task)
+                    .await
+                    .context("Failed to create session")?;
+
+                info!("Created session: {}", session_id);
+                self.session_id = Some(session_id.clone());
+                session_id
+            }
+        };
+
+        if let Some((_, ref mut meta)) = self.run_store {
+            meta.session_id = Some(session_id.clone());
+        }
+        self.save_run();
 
         // Subscribe to events
         let mut subscription = self
@@ -83,6 +310,10 @@ impl ControlLoop {
 
         info!("Subscribed to session events");
 
+        if let Some(ref mut diff_tracker) = self.diff_tracker {
+            diff_tracker.reset().await;
+        }
+
         // Main loop
         loop {
             // Check max iterations
@@ -113,40 +344,57 @@ impl ControlLoop {
             }
 
             // Stream events until review trigger
-            match self
-                .stream_until_review(&mut subscription, &event_sender)
+            let stream_end = match self
+                .stream_until_review(&session_id, &mut subscription, event_sender)
                 .await
             {
-                Ok(()) => {
-                    debug!("Stream ended or timeout, proceeding to review");
+                Ok(end) => {
+                    debug!("Stream stopped ({:?}), proceeding to review", end);
+                    end
                 }
                 Err(e) => {
                     error!("Error during streaming: {}", e);
                     // Continue to review what we have
+                    StreamEnd::Closed
                 }
-            }
+            };
+            let worker_idle = stream_end == StreamEnd::Idle;
 
             // Get the sample
             let sample = self.sampler.sample();
             let sample_size = self.sampler.line_count();
             debug!("Sample size: {} lines", sample_size);
 
-            if sample_size == 0 {
+            if sample_size == 0 && !worker_idle {
                 warn!("No output captured, waiting and retrying...");
                 tokio::time::sleep(Duration::from_secs(1)).await;
                 continue;
             }
 
+            let diff = match self.diff_tracker {
+                Some(ref mut diff_tracker) => diff_tracker.diff().await,
+                None => None,
+            };
+
             // Build reviewer context
             let context = ReviewerContext {
                 task_description: self.config.task.clone(),
                 iteration,
                 previous_summaries: self.state.get_previous_summaries(5),
                 current_sample: sample,
+                worker_idle,
+                verification: self
+                    .state
+                    .last_iteration()
+                    .map(|iter| iter.verification.clone())
+                    .unwrap_or_default(),
+                diff,
             };
 
             // Call reviewer (with retry)
-            let decision = self.reviewer.review_with_retry(&context).await?;
+            let outcome = self.reviewer.review_with_retry(&context).await?;
+            let decision = outcome.decision;
+            self.record(|recorder| recorder.record_review(&context, &decision));
 
             // Send decision to TUI
             if let Some(ref sender) = event_sender {
@@ -156,9 +404,13 @@ impl ControlLoop {
             }
 
             // Record the decision
-            let retry_count = 0; // TODO: Track actual retry count
             self.state
-                .record_decision(sample_size, decision.clone(), retry_count);
+                .record_decision(sample_size, decision.clone(), outcome.retries);
+            self.checkpoint(iteration, &decision).await;
+            // An abort is saved once its rollback has finished
+            if decision.action != ReviewerAction::Abort {
+                self.save_run();
+            }
 
             info!(
                 "Iteration {} decision: {:?} - {}",
@@ -167,15 +419,45 @@ impl ControlLoop {
 
             // Handle decision
             match decision.action {
+                ReviewerAction::Continue if worker_idle => {
+                    // Nothing more will arrive from an idle worker, so a clean
+                    // review of its final turn means the task is done
+                    info!("Worker is idle and reviewer found no problems, completing");
+                    if self.verify(&session_id, event_sender).await? {
+                        return Ok(RunResult::Completed);
+                    }
+                }
                 ReviewerAction::Continue => {
                     debug!("Continuing to next iteration");
                     // Clear sampler for next iteration
                     self.sampler.clear();
                 }
+                ReviewerAction::Complete => {
+                    info!("Task complete: {}", decision.reason);
+                    if self.verify(&session_id, event_sender).await? {
+                        return Ok(RunResult::Completed);
+                    }
+                }
                 ReviewerAction::Abort => {
                     info!("Aborting: {}", decision.reason);
+                    self.roll_back(&session_id).await;
+                    self.save_run();
                     return Ok(RunResult::Aborted(decision.reason));
                 }
+                ReviewerAction::Redirect { ref guidance } => {
+                    info!("Redirecting worker: {}", guidance);
+                    self.client
+                        .send_message(&session_id, guidance)
+                        .await
+                        .context("Failed to send reviewer guidance to session")?;
+
+                    if let Some(ref sender) = event_sender {
+                        let _ = sender.send(UiEvent::GuidanceSent(guidance.clone())).await;
+                    }
+
+                    // The worker is changing direction, start the next sample fresh
+                    self.sampler.clear();
+                }
             }
 
             // Small delay between iterations
@@ -184,11 +466,15 @@ impl ControlLoop {
     }
 
     /// Stream events until it's time to review
+    ///
+    /// Reconnects if the event stream drops, then backfills whatever the
+    /// sampler missed from the session's message history.
     async fn stream_until_review(
         &mut self,
+        session_id: &str,
         subscription: &mut SseSubscription,
         event_sender: &Option<mpsc::Sender<UiEvent>>,
-    ) -> Result<()> {
+    ) -> Result<StreamEnd> {
         let start_time = Instant::now();
         let mut last_event_time = Instant::now();
         let mut event_count = 0;
@@ -200,7 +486,7 @@ impl ControlLoop {
                     "Inactivity timeout after {:?}, triggering review",
                     self.config.inactivity_timeout
                 );
-                return Ok(());
+                return Ok(StreamEnd::Inactive);
             }
 
             // Use timeout to periodically check for inactivity
@@ -210,7 +496,11 @@ impl ControlLoop {
                     last_event_time = Instant::now();
 
                     // Process event in sampler
+                    self.record(|recorder| recorder.record_event(&event));
                     self.sampler.process_event(&event);
+                    if let Some((message_id, usage)) = message_usage(&event) {
+                        self.state.record_message_usage(message_id, usage);
+                    }
 
                     // Send to TUI if available
                     if let Some(ref sender) = event_sender {
@@ -222,15 +512,38 @@ impl ControlLoop {
                     }
 
                     // Check for natural completion indicators
+                    if is_idle_event(&event) {
+                        info!(
+                            "Session went idle after {} events in {:?}",
+                            event_count,
+                            start_time.elapsed()
+                        );
+                        return Ok(StreamEnd::Idle);
+                    }
                     if is_completion_event(&event) {
                         info!("Detected completion event");
-                        return Ok(());
+                        return Ok(StreamEnd::Trigger);
                     }
                 }
                 Ok(None) => {
-                    // Stream closed
-                    info!("Event stream closed");
-                    return Ok(());
+                    // Stream closed, reconnect and recover missed output
+                    warn!("Event stream closed, reconnecting");
+                    *subscription = self
+                        .client
+                        .subscribe(session_id)
+                        .await
+                        .context("Failed to reconnect to session events")?;
+                    self.backfill(session_id).await;
+
+                    if let Some(ref sender) = event_sender {
+                        let _ = sender
+                            .send(UiEvent::StatusUpdate(
+                                "Reconnected to event stream".to_string(),
+                            ))
+                            .await;
+                    }
+
+                    last_event_time = Instant::now();
                 }
                 Err(_) => {
                     // Timeout - continue loop to check inactivity
@@ -240,10 +553,187 @@ impl ControlLoop {
         }
     }
 
+    /// Recover output missed while the event stream was down
+    async fn backfill(&mut self, session_id: &str) {
+        match self.client.session_history(session_id).await {
+            Ok(history) => {
+                self.record(|recorder| recorder.record_history(&history.lines));
+                let added = self.sampler.backfill(&history.lines);
+                if added > 0 {
+                    info!("Backfilled {} lines missed while disconnected", added);
+                }
+            }
+            Err(e) => warn!("Could not backfill session history: {}", e),
+        }
+    }
+
+    /// Prompt that picks the task back up after an interruption
+    fn resume_prompt(&self) -> String {
+        let mut prompt = format!(
+            "The previous run was interrupted. Continue working on the task:\n\n{}",
+            self.config.task
+        );
+        if let Some((_, guidance)) = self.state.redirects().last() {
+            prompt.push_str(&format!("\n\nLast reviewer guidance: {}", guidance));
+        }
+        prompt
+    }
+
+    /// Snapshot the working tree for the decision just recorded
+    async fn checkpoint(&mut self, iteration: usize, decision: &ReviewerDecision) {
+        let Some(ref checkpoints) = self.checkpoints else {
+            return;
+        };
+
+        let message = format!(
+            "Iteration {}: {:?} - {}",
+            iteration, decision.action, decision.reason
+        );
+        match checkpoints.create(&message).await {
+            Ok(commit) => {
+                debug!("Iteration {} checkpoint: {}", iteration, commit);
+                self.state.record_checkpoint(commit);
+            }
+            Err(e) => warn!("Failed to checkpoint working tree: {:#}", e),
+        }
+    }
+
+    /// Snapshot the working tree before the worker starts
+    ///
+    /// A resumed run keeps the baseline taken when it first started.
+    async fn baseline_checkpoint(&mut self) {
+        let Some(ref checkpoints) = self.checkpoints else {
+            return;
+        };
+        if self.state.baseline_checkpoint().is_some() {
+            return;
+        }
+
+        match checkpoints.create("Baseline").await {
+            Ok(commit) => {
+                debug!("Baseline checkpoint: {}", commit);
+                self.state.record_baseline_checkpoint(commit);
+            }
+            Err(e) => warn!("Failed to checkpoint working tree: {:#}", e),
+        }
+    }
+
+    /// Run the verification commands before accepting completion
+    ///
+    /// Returns whether the run may complete. On failure the trimmed output
+    /// is sent to the worker so the next iteration picks up the fix.
+    async fn verify(
+        &mut self,
+        session_id: &str,
+        event_sender: &Option<mpsc::Sender<UiEvent>>,
+    ) -> Result<bool> {
+        let Some(ref verifier) = self.verifier else {
+            return Ok(true);
+        };
+
+        if let Some(ref sender) = event_sender {
+            let _ = sender
+                .send(UiEvent::StatusUpdate("Running verification".to_string()))
+                .await;
+        }
+
+        let results = verifier.run().await;
+        let passed = verify::all_passed(&results);
+        let message = verify::failure_message(&results);
+        self.state.record_verification(results);
+        self.save_run();
+
+        if passed {
+            info!("Verification passed");
+            return Ok(true);
+        }
+
+        warn!("Verification failed, sending output back to the worker");
+        self.client
+            .send_message(session_id, &message)
+            .await
+            .context("Failed to send verification failures to session")?;
+
+        if let Some(ref sender) = event_sender {
+            let _ = sender.send(UiEvent::GuidanceSent(message)).await;
+        }
+
+        // The worker is starting a new attempt, sample it fresh
+        self.sampler.clear();
+        Ok(false)
+    }
+
+    /// Stop the worker and restore the last checkpoint the reviewer approved,
+    /// or the baseline if it approved none
+    async fn roll_back(&self, session_id: &str) {
+        let Some(ref checkpoints) = self.checkpoints else {
+            return;
+        };
+        let Some(commit) = self
+            .state
+            .last_continue_checkpoint()
+            .or(self.state.baseline_checkpoint())
+        else {
+            info!("No checkpoint to roll back to");
+            return;
+        };
+
+        // The worker must stop editing before its changes are undone
+        if let Err(e) = self.client.abort_session(session_id).await {
+            warn!("Failed to abort session before rolling back: {}", e);
+        }
+        if let Err(e) = checkpoints.restore(commit).await {
+            warn!("Failed to roll back to checkpoint {}: {:#}", commit, e);
+        }
+    }
+
+    /// Write to the recorder if one is configured
+    fn record<F>(&mut self, write: F)
+    where
+        F: FnOnce(&mut EventRecorder) -> Result<()>,
+    {
+        if let Some(ref mut recorder) = self.recorder {
+            if let Err(e) = write(recorder) {
+                warn!("Failed to record events: {:#}", e);
+            }
+        }
+    }
+
+    /// Save the run if a run store is configured
+    fn save_run(&self) {
+        if let Some((ref store, ref meta)) = self.run_store {
+            if let Err(e) = store.save(meta, &self.state) {
+                warn!("Failed to save run state: {}", e);
+            }
+        }
+    }
+
     /// Get current state reference (for TUI)
     pub fn state(&self) -> &State {
         &self.state
     }
+
+    /// Get the control loop configuration
+    pub fn config(&self) -> &ControlConfig {
+        &self.config
+    }
+
+    /// Get the saved run's metadata, if a run store is configured
+    pub fn run_meta(&self) -> Option<&RunMeta> {
+        self.run_store.as_ref().map(|(_, meta)| meta)
+    }
+
+    /// Get the id of the saved run, if a run store is configured
+    pub fn run_id(&self) -> Option<&str> {
+        self.run_store
+            .as_ref()
+            .map(|(_, meta)| meta.run_id.as_str())
+    }
+
+    /// Get the session id, once the session exists
+    pub fn session_id(&self) -> Option<&str> {
+        self.session_id.as_deref()
+    }
 }
 
 /// Check if an event indicates task completion
@@ -257,6 +747,38 @@ fn is_completion_event(event: &opencode_rs::types::event::Event) -> bool {
     }
 }
 
+/// Check if an event indicates the session finished its turn and is idle
+fn is_idle_event(event: &opencode_rs::types::event::Event) -> bool {
+    use opencode_rs::types::event::Event;
+
+    match event {
+        Event::SessionIdle { .. } => true,
+        Event::SessionStatus { properties } => serde_json::to_value(properties)
+            .ok()
+            .and_then(|props| {
+                props
+                    .pointer("/status/type")
+                    .and_then(|v| v.as_str())
+                    .map(|status| status == "idle")
+            })
+            .unwrap_or(false),
+        _ => false,
+    }
+}
+
+/// Extract the worker's token usage from a message update
+fn message_usage(event: &opencode_rs::types::event::Event) -> Option<(String, TokenUsage)> {
+    use opencode_rs::types::event::Event;
+
+    match event {
+        Event::MessageUpdated { properties } => {
+            let props = serde_json::to_value(properties).ok()?;
+            TokenUsage::from_message_info(props.get("info")?)
+        }
+        _ => None,
+    }
+}
+
 /// Check if an event should be sent to the UI
 fn should_send_to_ui(event: &opencode_rs::types::event::Event) -> bool {
     use opencode_rs::types::event::Event;
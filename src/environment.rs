@@ -1,77 +1,98 @@
 use anyhow::{Context, Result};
 use std::path::PathBuf;
+use std::str::FromStr;
 
-use crate::config::ControlConfig;
+use crate::config::ConfigLayer;
+use crate::reviewer::ReviewerBackend;
 
-/// Load configuration from environment variables
-pub fn load_config_from_env() -> Result<ControlConfig> {
-    let task = std::env::var("OPCODE_TASK").unwrap_or_else(|_| "".to_string());
-    let working_dir = std::env::var("OPCODE_WORKING_DIR")
-        .unwrap_or_else(|_| ".".to_string())
-        .parse::<PathBuf>()?;
-    let worker_model =
-        std::env::var("OPCODE_WORKER_MODEL").unwrap_or_else(|_| "ollama/llama3.1".to_string());
-    let reviewer_url = std::env::var("OPCODE_REVIEWER_URL")
-        .unwrap_or_else(|_| "http://localhost:11434/v1".to_string());
-    let reviewer_model =
-        std::env::var("OPCODE_REVIEWER_MODEL").unwrap_or_else(|_| "ollama/llama3.1".to_string());
-    let max_iterations = std::env::var("OPCODE_MAX_ITERATIONS")
-        .unwrap_or_else(|_| "10".to_string())
-        .parse()
-        .unwrap_or(10);
-    let inactivity_timeout = std::env::var("OPCODE_INACTIVITY_TIMEOUT")
-        .unwrap_or_else(|_| "30".to_string())
-        .parse()
-        .unwrap_or(30);
-    let headless = std::env::var("OPCODE_HEADLESS")
-        .unwrap_or_else(|_| "false".to_string())
-        .parse()
-        .unwrap_or(false);
+/// Load the environment layer of the runner configuration
+///
+/// Only variables that are set are returned; defaults come from
+/// [`RunnerConfig`](crate::config::RunnerConfig).
+pub fn load_config_from_env() -> Result<ConfigLayer> {
+    let reviewer_backend = match env_var("OPCODE_REVIEWER_BACKEND") {
+        Some(value) => Some(
+            <ReviewerBackend as clap::ValueEnum>::from_str(&value, true)
+                .map_err(|e| anyhow::anyhow!("Invalid OPCODE_REVIEWER_BACKEND: {}", e))?,
+        ),
+        None => None,
+    };
+    let headless = match env_var("OPCODE_HEADLESS") {
+        Some(value) => Some(parse_bool(&value).context("Invalid OPCODE_HEADLESS")?),
+        None => None,
+    };
+    let worktree = match env_var("OPCODE_WORKTREE") {
+        Some(value) => Some(parse_bool(&value).context("Invalid OPCODE_WORKTREE")?),
+        None => None,
+    };
+    let checkpoints = match env_var("OPCODE_CHECKPOINTS") {
+        Some(value) => Some(parse_bool(&value).context("Invalid OPCODE_CHECKPOINTS")?),
+        None => None,
+    };
+    let record_events = match env_var("OPCODE_RECORD_EVENTS") {
+        Some(value) => Some(parse_bool(&value).context("Invalid OPCODE_RECORD_EVENTS")?),
+        None => None,
+    };
 
-    // Validate required environment variables
-    if task.is_empty() {
-        anyhow::bail!("OPCODE_TASK environment variable is required");
-    }
-
-    Ok(ControlConfig {
-        task,
-        max_iterations,
-        inactivity_timeout: std::time::Duration::from_secs(inactivity_timeout),
+    Ok(ConfigLayer {
+        task: env_var("OPCODE_TASK"),
+        working_dir: env_var("OPCODE_WORKING_DIR").map(PathBuf::from),
+        worker_model: env_var("OPCODE_WORKER_MODEL"),
+        reviewer_backend,
+        reviewer_url: env_var("OPCODE_REVIEWER_URL"),
+        reviewer_model: env_var("OPCODE_REVIEWER_MODEL"),
+        reviewer_system_prompt: env_var("OPCODE_REVIEWER_SYSTEM_PROMPT"),
+        reviewer_system_prompt_file: env_var("OPCODE_REVIEWER_SYSTEM_PROMPT_FILE")
+            .map(PathBuf::from),
+        reviewer_prompt_template: env_var("OPCODE_REVIEWER_PROMPT_TEMPLATE").map(PathBuf::from),
+        reviewer_token_budget: parse_env_var("OPCODE_REVIEWER_TOKEN_BUDGET")?,
+        max_iterations: parse_env_var("OPCODE_MAX_ITERATIONS")?,
+        inactivity_timeout: parse_env_var("OPCODE_INACTIVITY_TIMEOUT")?,
+        sample_lines: parse_env_var("OPCODE_SAMPLE_LINES")?,
+        headless,
+        server_url: env_var("OPCODE_SERVER_URL"),
+        runs_dir: env_var("OPCODE_RUNS_DIR").map(PathBuf::from),
+        worktree,
+        worktrees_dir: env_var("OPCODE_WORKTREES_DIR").map(PathBuf::from),
+        checkpoints,
+        record_events,
+        // One command per line
+        verify: env_var("OPCODE_VERIFY").map(|commands| {
+            commands
+                .lines()
+                .map(str::trim)
+                .filter(|command| !command.is_empty())
+                .map(String::from)
+                .collect()
+        }),
+        verify_timeout: parse_env_var("OPCODE_VERIFY_TIMEOUT")?,
+        jobs: parse_env_var("OPCODE_JOBS")?,
+        reviewer_max_concurrent: parse_env_var("OPCODE_REVIEWER_MAX_CONCURRENT")?,
+        reviewer_min_interval_ms: parse_env_var("OPCODE_REVIEWER_MIN_INTERVAL_MS")?,
     })
 }
 
-/// Validate that all required environment variables are set
-pub fn validate_env_vars() -> Result<()> {
-    let task =
-        std::env::var("OPCODE_TASK").context("OPCODE_TASK environment variable is required")?;
-    if task.is_empty() {
-        anyhow::bail!("OPCODE_TASK cannot be empty");
-    }
-
-    Ok(())
+/// Non-empty environment variable
+fn env_var(key: &str) -> Option<String> {
+    std::env::var(key).ok().filter(|value| !value.is_empty())
 }
 
-/// Get environment variable or fallback to default
-pub fn get_env_with_default(key: &str, default: &str) -> String {
-    std::env::var(key).unwrap_or_else(|_| default.to_string())
+/// Parse an environment variable if it is set
+fn parse_env_var<T>(key: &str) -> Result<Option<T>>
+where
+    T: FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    env_var(key)
+        .map(|value| value.parse::<T>())
+        .transpose()
+        .with_context(|| format!("Invalid {}", key))
 }
 
-/// Get environment variable as integer with fallback
-pub fn get_env_with_default_int(key: &str, default: i64) -> i64 {
-    std::env::var(key)
-        .ok()
-        .and_then(|s| s.parse().ok())
-        .unwrap_or(default)
-}
-
-/// Get environment variable as boolean with fallback
-pub fn get_env_with_default_bool(key: &str, default: bool) -> bool {
-    std::env::var(key)
-        .ok()
-        .and_then(|s| match s.to_lowercase().as_str() {
-            "1" | "true" | "yes" => Some(true),
-            "0" | "false" | "no" => Some(false),
-            _ => None,
-        })
-        .unwrap_or(default)
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.to_lowercase().as_str() {
+        "1" | "true" | "yes" => Some(true),
+        "0" | "false" | "no" => Some(false),
+        _ => None,
+    }
 }
@@ -0,0 +1,296 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+use crate::{
+    recording::{self, ReplayStep},
+    report::write_report,
+    reviewer::{Reviewer, ReviewerAction},
+};
+
+/// File in an eval directory labelling its transcripts
+pub const LABELS_FILE: &str = "labels.toml";
+
+/// What the reviewer should have done over a transcript
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Expected {
+    /// The worker was on track, no review should abort
+    Continue,
+    /// The worker got stuck, a review at or after `iteration` should abort
+    Abort,
+}
+
+/// Correct outcome for one transcript
+///
+/// ```toml
+/// [stuck-in-loop]
+/// expect = "abort"
+/// iteration = 4
+///
+/// [adds-endpoint]
+/// expect = "continue"
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Label {
+    pub expect: Expected,
+    /// First iteration where aborting is correct, required for `abort`
+    #[serde(default)]
+    pub iteration: Option<usize>,
+}
+
+impl Label {
+    /// Whether aborting at `iteration` is the right call
+    fn abort_is_correct(&self, iteration: usize) -> bool {
+        match (self.expect, self.iteration) {
+            (Expected::Abort, Some(from)) => iteration >= from,
+            _ => false,
+        }
+    }
+}
+
+/// Read and check `labels.toml`, keyed by transcript name
+pub fn load_labels(dir: &Path) -> Result<BTreeMap<String, Label>> {
+    let path = dir.join(LABELS_FILE);
+    let contents = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let labels: BTreeMap<String, Label> =
+        toml::from_str(&contents).with_context(|| format!("Failed to parse {}", path.display()))?;
+
+    for (name, label) in &labels {
+        match (label.expect, label.iteration) {
+            (Expected::Abort, None) => {
+                anyhow::bail!("Label '{}' expects abort but has no iteration", name)
+            }
+            (Expected::Continue, Some(_)) => {
+                anyhow::bail!("Label '{}' expects continue but has an iteration", name)
+            }
+            _ => {}
+        }
+    }
+    Ok(labels)
+}
+
+/// How the reviewer's first abort compared with the label
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    /// Aborted at or after the labelled iteration
+    TruePositive,
+    /// Aborted a transcript that should continue, or aborted too early
+    FalsePositive,
+    /// Never aborted a transcript that should abort
+    FalseNegative,
+    /// Never aborted a transcript that should continue
+    TrueNegative,
+}
+
+/// Reviewer performance on one transcript
+#[derive(Debug, Clone, Serialize)]
+pub struct TranscriptResult {
+    pub name: String,
+    pub label: Label,
+    /// Iteration of the reviewer's first abort
+    pub aborted_at: Option<usize>,
+    pub outcome: Outcome,
+    /// Reviews asked for, up to and including the first abort
+    pub reviews: usize,
+    /// Reviews where continuing was correct
+    pub continue_reviews: usize,
+    /// Reviews where continuing was correct but the reviewer aborted
+    pub false_aborts: usize,
+    /// Time taken by each review
+    pub latencies_ms: Vec<u64>,
+}
+
+impl TranscriptResult {
+    /// Score the replayed reviews of a transcript against its label
+    pub fn score(name: &str, label: Label, steps: &[ReplayStep]) -> Self {
+        let aborted_at = steps
+            .iter()
+            .find(|step| step.replayed.action == ReviewerAction::Abort)
+            .map(|step| step.iteration);
+
+        let outcome = match (aborted_at, label.expect) {
+            (Some(iteration), _) if label.abort_is_correct(iteration) => Outcome::TruePositive,
+            (Some(_), _) => Outcome::FalsePositive,
+            (None, Expected::Abort) => Outcome::FalseNegative,
+            (None, Expected::Continue) => Outcome::TrueNegative,
+        };
+
+        let continue_steps = steps
+            .iter()
+            .filter(|step| !label.abort_is_correct(step.iteration));
+
+        Self {
+            name: name.to_string(),
+            label,
+            aborted_at,
+            outcome,
+            reviews: steps.len(),
+            continue_reviews: continue_steps.clone().count(),
+            false_aborts: continue_steps
+                .filter(|step| step.replayed.action == ReviewerAction::Abort)
+                .count(),
+            latencies_ms: steps.iter().map(|step| step.latency_ms).collect(),
+        }
+    }
+}
+
+/// Review latency across all transcripts
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct LatencyStats {
+    pub mean_ms: u64,
+    pub p50_ms: u64,
+    pub p95_ms: u64,
+    pub max_ms: u64,
+}
+
+impl LatencyStats {
+    pub fn from_samples(samples: &[u64]) -> Self {
+        if samples.is_empty() {
+            return Self::default();
+        }
+        let mut sorted = samples.to_vec();
+        sorted.sort_unstable();
+        let percentile = |p: usize| sorted[(sorted.len() * p).div_ceil(100).max(1) - 1];
+
+        Self {
+            mean_ms: sorted.iter().sum::<u64>() / sorted.len() as u64,
+            p50_ms: percentile(50),
+            p95_ms: percentile(95),
+            max_ms: sorted[sorted.len() - 1],
+        }
+    }
+}
+
+/// Abort precision and recall, false-abort rate and latency of a reviewer
+/// over a set of labelled transcripts
+#[derive(Debug, Clone, Serialize)]
+pub struct EvalReport {
+    /// Share of aborts that were correct, `None` without any aborts
+    pub precision: Option<f64>,
+    /// Share of should-abort transcripts the reviewer aborted correctly,
+    /// `None` without any
+    pub recall: Option<f64>,
+    /// Share of should-continue reviews the reviewer aborted, `None`
+    /// without any
+    pub false_abort_rate: Option<f64>,
+    pub latency: LatencyStats,
+    pub transcripts: Vec<TranscriptResult>,
+}
+
+impl EvalReport {
+    pub fn new(transcripts: Vec<TranscriptResult>) -> Self {
+        let count = |outcome: Outcome| {
+            transcripts
+                .iter()
+                .filter(|result| result.outcome == outcome)
+                .count()
+        };
+        let true_positives = count(Outcome::TruePositive);
+        let false_positives = count(Outcome::FalsePositive);
+        let should_abort = transcripts
+            .iter()
+            .filter(|result| result.label.expect == Expected::Abort)
+            .count();
+        let continue_reviews = transcripts.iter().map(|r| r.continue_reviews).sum();
+        let false_aborts = transcripts.iter().map(|r| r.false_aborts).sum();
+        let latencies: Vec<u64> = transcripts
+            .iter()
+            .flat_map(|result| result.latencies_ms.iter().copied())
+            .collect();
+
+        Self {
+            precision: ratio(true_positives, true_positives + false_positives),
+            recall: ratio(true_positives, should_abort),
+            false_abort_rate: ratio(false_aborts, continue_reviews),
+            latency: LatencyStats::from_samples(&latencies),
+            transcripts,
+        }
+    }
+
+    /// One-line summary of the metrics
+    pub fn format_summary(&self) -> String {
+        format!(
+            "{} transcripts | Precision: {} | Recall: {} | False aborts: {} | Latency: {}ms mean, {}ms p95",
+            self.transcripts.len(),
+            format_ratio(self.precision),
+            format_ratio(self.recall),
+            format_ratio(self.false_abort_rate),
+            self.latency.mean_ms,
+            self.latency.p95_ms
+        )
+    }
+
+    /// Write the report as pretty-printed JSON
+    pub fn write_json(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self).context("Failed to serialize eval report")?;
+        write_report(path, &json)
+    }
+}
+
+/// Replay every labelled transcript in `dir`, each through a fresh
+/// reviewer from `make_reviewer`
+///
+/// Transcripts are `<name>.jsonl` recordings next to `labels.toml`. Like
+/// a live run, each is replayed until the reviewer's first abort, and no
+/// reviewer history carries over from other transcripts.
+pub async fn evaluate<F>(dir: &Path, make_reviewer: F, sample_lines: usize) -> Result<EvalReport>
+where
+    F: Fn() -> Result<Arc<dyn Reviewer>>,
+{
+    let labels = load_labels(dir)?;
+
+    let mut unlabelled = Vec::new();
+    for entry in
+        std::fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))?
+    {
+        let path = entry?.path();
+        let name = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or_default();
+        if path.extension().is_some_and(|ext| ext == "jsonl") && !labels.contains_key(name) {
+            unlabelled.push(name.to_string());
+        }
+    }
+    if !unlabelled.is_empty() {
+        unlabelled.sort();
+        warn!("Skipping unlabelled transcripts: {}", unlabelled.join(", "));
+    }
+
+    let mut results = Vec::new();
+    for (name, label) in labels {
+        let path = dir.join(format!("{}.jsonl", name));
+        let entries = recording::load_recording(&path)
+            .with_context(|| format!("Failed to load transcript '{}'", name))?;
+        let reviewer = make_reviewer()?;
+        let steps =
+            recording::replay_until_abort(&entries, reviewer.as_ref(), sample_lines).await?;
+
+        let result = TranscriptResult::score(&name, label, &steps);
+        info!(
+            "{}: {:?} after {} reviews",
+            result.name, result.outcome, result.reviews
+        );
+        results.push(result);
+    }
+
+    Ok(EvalReport::new(results))
+}
+
+fn ratio(numerator: usize, denominator: usize) -> Option<f64> {
+    (denominator > 0).then(|| numerator as f64 / denominator as f64)
+}
+
+fn format_ratio(value: Option<f64>) -> String {
+    match value {
+        Some(value) => format!("{:.1}%", value * 100.0),
+        None => "n/a".to_string(),
+    }
+}
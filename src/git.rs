@@ -0,0 +1,397 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+use tracing::{debug, info, warn};
+
+/// Identity used for checkpoint commits, so they work without git config
+const CHECKPOINT_IDENTITY: [(&str, &str); 4] = [
+    ("GIT_AUTHOR_NAME", "opencode_runner"),
+    ("GIT_AUTHOR_EMAIL", "opencode_runner@localhost"),
+    ("GIT_COMMITTER_NAME", "opencode_runner"),
+    ("GIT_COMMITTER_EMAIL", "opencode_runner@localhost"),
+];
+
+/// Run git in `dir`, returning its trimmed stdout
+pub async fn run_git(dir: &Path, args: &[&str]) -> Result<String> {
+    run_git_with_env(dir, args, &[]).await
+}
+
+/// Run git in `dir` with extra environment variables
+pub async fn run_git_with_env(dir: &Path, args: &[&str], env: &[(&str, &str)]) -> Result<String> {
+    debug!("git {} (in {})", args.join(" "), dir.display());
+
+    let output = Command::new("git")
+        .args(args)
+        .envs(env.iter().copied())
+        .current_dir(dir)
+        .output()
+        .await
+        .context("Failed to run git. Is git installed and in PATH?")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Keep git away from everything in `dir` by giving it a `.gitignore`
+/// that ignores all of its contents
+///
+/// Used for the runner's own state, so run records and worktrees never
+/// end up in checkpoints, diffs or worktree commits.
+pub fn ignore_dir(dir: &Path) -> Result<()> {
+    let path = dir.join(".gitignore");
+    if !path.exists() {
+        std::fs::write(&path, "*\n")
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// Root of the repository containing `dir`
+pub async fn repo_root(dir: &Path) -> Result<PathBuf> {
+    let root = run_git(dir, &["rev-parse", "--show-toplevel"])
+        .await
+        .with_context(|| format!("{} is not inside a git repository", dir.display()))?;
+    Ok(PathBuf::from(root))
+}
+
+/// A git worktree checked out on its own branch for a single run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Worktree {
+    /// Root of the main repository
+    pub repo: PathBuf,
+    /// Directory of the worktree
+    pub path: PathBuf,
+    /// Branch checked out in the worktree
+    pub branch: String,
+}
+
+impl Worktree {
+    /// Create a worktree of the repository containing `dir` at
+    /// `worktrees_dir/<name>`, on a new branch `opencode_runner/<name>`
+    /// starting from the current `HEAD`
+    pub async fn create(dir: &Path, worktrees_dir: &Path, name: &str) -> Result<Self> {
+        let repo = repo_root(dir).await?;
+        std::fs::create_dir_all(worktrees_dir).with_context(|| {
+            format!(
+                "Failed to create worktrees directory {}",
+                worktrees_dir.display()
+            )
+        })?;
+        ignore_dir(worktrees_dir)?;
+        let path = worktrees_dir
+            .canonicalize()
+            .with_context(|| format!("Failed to resolve {}", worktrees_dir.display()))?
+            .join(name);
+        let branch = format!("opencode_runner/{}", name);
+
+        let path_arg = path.to_string_lossy();
+        run_git(
+            &repo,
+            &["worktree", "add", "-b", &branch, &path_arg, "HEAD"],
+        )
+        .await
+        .context("Failed to create worktree")?;
+        info!("Created worktree {} on branch {}", path.display(), branch);
+
+        Ok(Self { repo, path, branch })
+    }
+
+    /// The directory in the worktree corresponding to `dir` in the main
+    /// repository, so a run started in a subdirectory stays there
+    pub fn map_dir(&self, dir: &Path) -> PathBuf {
+        dir.canonicalize()
+            .ok()
+            .and_then(|dir| {
+                dir.strip_prefix(&self.repo)
+                    .ok()
+                    .map(|relative| self.path.join(relative))
+            })
+            .unwrap_or_else(|| self.path.clone())
+    }
+
+    /// Commit everything the worker changed to the worktree's branch
+    ///
+    /// Returns `false` if there was nothing to commit.
+    pub async fn commit_all(&self, message: &str) -> Result<bool> {
+        run_git(&self.path, &["add", "--all"]).await?;
+        let status = run_git(&self.path, &["status", "--porcelain"]).await?;
+        if status.is_empty() {
+            return Ok(false);
+        }
+        run_git(&self.path, &["commit", "--quiet", "--message", message]).await?;
+        Ok(true)
+    }
+
+    /// Keep the run's work: commit it to the branch and remove the worktree
+    ///
+    /// If committing fails the worktree is left in place so nothing is lost.
+    pub async fn keep(self, message: &str) -> Result<()> {
+        if let Err(e) = self.commit_all(message).await {
+            warn!(
+                "Could not commit worktree changes, leaving {} in place: {:#}",
+                self.path.display(),
+                e
+            );
+            return Ok(());
+        }
+        self.remove_worktree().await?;
+        info!("Kept branch {}", self.branch);
+        Ok(())
+    }
+
+    /// Throw the run's work away: remove the worktree and delete its branch
+    pub async fn discard(self) -> Result<()> {
+        self.remove_worktree().await?;
+        run_git(&self.repo, &["branch", "-D", &self.branch])
+            .await
+            .context("Failed to delete worktree branch")?;
+        info!("Discarded worktree branch {}", self.branch);
+        Ok(())
+    }
+
+    async fn remove_worktree(&self) -> Result<()> {
+        let path_arg = self.path.to_string_lossy();
+        run_git(&self.repo, &["worktree", "remove", "--force", &path_arg])
+            .await
+            .context("Failed to remove worktree")?;
+        Ok(())
+    }
+}
+
+/// Snapshots of a working tree stored as commits under a private ref
+///
+/// Snapshots use a temporary index, so the user's index, `HEAD` and
+/// branch are never touched. Each checkpoint's parent is the previous
+/// one, so the ref holds the run's full history. Ignored files, which
+/// include the runner's own state (see `ignore_dir`), are neither
+/// snapshotted nor restored.
+pub struct Checkpoints {
+    dir: PathBuf,
+    name: String,
+    ref_name: String,
+}
+
+impl Checkpoints {
+    /// Checkpoints of `dir`, stored under `refs/opencode_runner/checkpoints/<name>`
+    pub fn new(dir: PathBuf, name: &str) -> Self {
+        Self {
+            dir,
+            name: name.to_string(),
+            ref_name: format!("refs/opencode_runner/checkpoints/{}", name),
+        }
+    }
+
+    /// Ref the checkpoints are stored under
+    pub fn ref_name(&self) -> &str {
+        &self.ref_name
+    }
+
+    /// Commit the current working tree (including untracked files that are
+    /// not ignored), returning the commit id
+    pub async fn create(&self, message: &str) -> Result<String> {
+        let tree = self.snapshot_tree().await?;
+        let parent = match run_git(
+            &self.dir,
+            &["rev-parse", "--verify", "--quiet", &self.ref_name],
+        )
+        .await
+        {
+            Ok(previous) => previous,
+            Err(_) => run_git(&self.dir, &["rev-parse", "HEAD"]).await?,
+        };
+
+        let commit = run_git_with_env(
+            &self.dir,
+            &["commit-tree", &tree, "-p", &parent, "-m", message],
+            &CHECKPOINT_IDENTITY,
+        )
+        .await
+        .context("Failed to create checkpoint commit")?;
+        run_git(&self.dir, &["update-ref", &self.ref_name, &commit]).await?;
+
+        debug!("Checkpoint {} ({})", commit, message);
+        Ok(commit)
+    }
+
+    /// Put the working tree back to `commit`
+    ///
+    /// Files created since the checkpoint are deleted and everything else
+    /// is restored from it; the index is left alone.
+    pub async fn restore(&self, commit: &str) -> Result<()> {
+        let current = self.snapshot_tree().await?;
+        let added = run_git(
+            &self.dir,
+            &[
+                "diff",
+                "--name-only",
+                "--no-renames",
+                "--diff-filter=A",
+                commit,
+                &current,
+            ],
+        )
+        .await?;
+
+        // Paths from git are relative to the repository root
+        let root = repo_root(&self.dir).await?;
+        for path in added.lines().filter(|line| !line.is_empty()) {
+            let path = root.join(path);
+            if let Err(e) = std::fs::remove_file(&path) {
+                warn!("Could not remove {}: {}", path.display(), e);
+            }
+        }
+
+        let source = format!("--source={}", commit);
+        run_git(&root, &["restore", &source, "--worktree", "--", "."])
+            .await
+            .context("Failed to restore checkpoint")?;
+
+        info!("Rolled back working tree to checkpoint {}", commit);
+        Ok(())
+    }
+
+    /// Write the working tree as a tree object
+    async fn snapshot_tree(&self) -> Result<String> {
+        snapshot_tree(&self.dir, &format!("opencode_runner-{}.index", self.name)).await
+    }
+}
+
+/// Most bytes of patch text kept in a `TreeDiff`
+const MAX_DIFF_BYTES: usize = 8000;
+
+/// Changes to the working tree between two reviews
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TreeDiff {
+    /// `git diff --stat` output, empty when nothing changed
+    pub stat: String,
+    /// Unified diff, cut short at `MAX_DIFF_BYTES`
+    pub patch: String,
+    /// Whether `patch` was cut short
+    pub truncated: bool,
+    /// Consecutive reviews, including this one, that saw no changes
+    pub unchanged_reviews: usize,
+}
+
+impl TreeDiff {
+    /// Whether no files changed
+    pub fn is_empty(&self) -> bool {
+        self.stat.is_empty()
+    }
+}
+
+/// Tracks what the worker changed in the working tree between reviews
+///
+/// Like `Checkpoints`, snapshots go through a temporary index and leave
+/// the repository's index and branches alone, and ignored files such as
+/// the runner's own state are left out.
+pub struct DiffTracker {
+    dir: PathBuf,
+    index_name: String,
+    /// Tree the next diff is taken against
+    baseline: Option<String>,
+    unchanged_reviews: usize,
+}
+
+impl DiffTracker {
+    /// Tracker for the repository containing `dir`; `name` keeps its
+    /// temporary index apart from concurrent runs
+    pub fn new(dir: PathBuf, name: &str) -> Self {
+        Self {
+            dir,
+            index_name: format!("opencode_runner-{}-diff.index", name),
+            baseline: None,
+            unchanged_reviews: 0,
+        }
+    }
+
+    /// Take the current working tree as the baseline
+    ///
+    /// Tracking stays off if `dir` is not in a git repository.
+    pub async fn reset(&mut self) {
+        self.unchanged_reviews = 0;
+        self.baseline = match snapshot_tree(&self.dir, &self.index_name).await {
+            Ok(tree) => Some(tree),
+            Err(e) => {
+                debug!("Not tracking working tree changes: {:#}", e);
+                None
+            }
+        };
+    }
+
+    /// Changes since the baseline, which then moves to the current tree
+    pub async fn diff(&mut self) -> Option<TreeDiff> {
+        let baseline = self.baseline.clone()?;
+        let current = match snapshot_tree(&self.dir, &self.index_name).await {
+            Ok(tree) => tree,
+            Err(e) => {
+                warn!("Failed to snapshot working tree: {:#}", e);
+                return None;
+            }
+        };
+        self.baseline = Some(current.clone());
+
+        match self.diff_trees(&baseline, &current).await {
+            Ok(diff) => Some(diff),
+            Err(e) => {
+                warn!("Failed to diff working tree: {:#}", e);
+                None
+            }
+        }
+    }
+
+    async fn diff_trees(&mut self, from: &str, to: &str) -> Result<TreeDiff> {
+        let stat = run_git(&self.dir, &["diff", "--stat", from, to]).await?;
+        if stat.is_empty() {
+            self.unchanged_reviews += 1;
+            return Ok(TreeDiff {
+                unchanged_reviews: self.unchanged_reviews,
+                ..Default::default()
+            });
+        }
+        self.unchanged_reviews = 0;
+
+        let mut patch = run_git(&self.dir, &["diff", "--no-color", from, to]).await?;
+        let truncated = patch.len() > MAX_DIFF_BYTES;
+        if truncated {
+            let mut end = MAX_DIFF_BYTES;
+            while !patch.is_char_boundary(end) {
+                end -= 1;
+            }
+            patch.truncate(end);
+        }
+
+        Ok(TreeDiff {
+            stat,
+            patch,
+            truncated,
+            unchanged_reviews: 0,
+        })
+    }
+}
+
+/// Write the working tree of the repository containing `dir` as a tree
+/// object, using a throwaway index named `index_name`
+async fn snapshot_tree(dir: &Path, index_name: &str) -> Result<String> {
+    let index = run_git(dir, &["rev-parse", "--git-path", index_name]).await?;
+    let index = dir.join(index);
+    let index_str = index.to_string_lossy().to_string();
+    let env = [("GIT_INDEX_FILE", index_str.as_str())];
+
+    let tree = async {
+        run_git_with_env(dir, &["read-tree", "HEAD"], &env).await?;
+        run_git_with_env(dir, &["add", "--all", ":/"], &env).await?;
+        run_git_with_env(dir, &["write-tree"], &env).await
+    }
+    .await;
+
+    let _ = std::fs::remove_file(&index);
+    tree.context("Failed to snapshot working tree")
+}
@@ -1,23 +1,39 @@
 // Library exports for testing
 // This allows tests to import modules from src/
 
+pub mod batch;
 pub mod client;
 pub mod config;
 pub mod control_loop;
 pub mod environment;
+pub mod eval;
+pub mod git;
+pub mod recording;
+pub mod report;
 pub mod reviewer;
+pub mod run_store;
 pub mod sampler;
 pub mod server;
 pub mod state;
+pub mod verify;
 
 #[cfg(feature = "tui")]
 pub mod tui;
 
+pub use batch::{BatchReport, BatchTask};
 pub use client::OpenCodeClient;
-pub use config::ControlConfig;
+pub use config::{ConfigFile, ConfigLayer, ControlConfig, RunnerConfig};
 pub use control_loop::{ControlLoop, RunResult};
 pub use environment::load_config_from_env;
-pub use reviewer::{ReviewerAction, ReviewerClient, ReviewerContext, ReviewerDecision};
+pub use git::{DiffTracker, TreeDiff, Worktree};
+pub use recording::{EventRecorder, RecordEntry};
+pub use report::RunReport;
+pub use reviewer::{
+    ReviewOutcome, Reviewer, ReviewerAction, ReviewerBackend, ReviewerClient, ReviewerContext,
+    ReviewerDecision,
+};
+pub use run_store::{RunMeta, RunStore};
 pub use sampler::{Sampler, SamplerEvent};
 pub use server::ServerManager;
-pub use state::State;
+pub use state::{State, TokenUsage};
+pub use verify::{VerificationResult, Verifier};
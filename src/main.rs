@@ -1,70 +1,249 @@
-use anyhow::Result;
-use clap::Parser;
-use std::path::PathBuf;
+use anyhow::{Context, Result};
+use chrono::Utc;
+use clap::{Parser, Subcommand};
+use futures::StreamExt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
 use tracing::{info, warn};
 
+mod batch;
 mod client;
 mod config;
 mod control_loop;
 mod environment;
+mod eval;
+mod git;
+mod recording;
+mod report;
 mod reviewer;
+mod run_store;
 mod sampler;
 mod server;
 mod state;
+mod verify;
 
 #[cfg(feature = "tui")]
 mod tui;
 
+use batch::{BatchError, BatchReport, BatchTask};
 use client::OpenCodeClient;
-use config::ControlConfig;
+use config::{ConfigLayer, ControlConfig, RunnerConfig};
 use control_loop::{ControlLoop, RunResult};
-use environment::load_config_from_env;
-use reviewer::ReviewerClient;
+use git::{Checkpoints, DiffTracker, Worktree};
+use recording::{EventRecorder, ReplayReport};
+use report::{RunReport, RunSettings};
+use reviewer::{RateLimitedReviewer, RateLimiter, ReviewerBackend, ReviewerOptions};
+use run_store::{generate_run_id, RunMeta, RunStore};
 use sampler::Sampler;
 use server::ServerManager;
 use state::State;
+use verify::Verifier;
+
+/// Exit code for runs stopped by SIGINT/SIGTERM (128 + SIGINT)
+const EXIT_INTERRUPTED: i32 = 130;
 
 #[derive(Parser, Debug)]
 #[command(name = "opencode_runner")]
 #[command(about = "Control loop for OpenCode agent execution with review")]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Config file to use instead of ./opencode_runner.toml
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+
+    /// Named profile from the config file(s)
+    #[arg(long, global = true)]
+    profile: Option<String>,
+
     /// Task description for the worker
-    #[arg(short, long)]
-    task: String,
+    #[arg(short, long, global = true)]
+    task: Option<String>,
+
+    /// Working directory for the task [default: .]
+    #[arg(short, long, global = true)]
+    working_dir: Option<PathBuf>,
+
+    /// Model for the worker (e.g., "ollama/llama3.1") [default: ollama/llama3.1]
+    #[arg(long, global = true)]
+    worker_model: Option<String>,
+
+    /// Reviewer backend [default: openai]
+    #[arg(long, value_enum, global = true)]
+    reviewer_backend: Option<ReviewerBackend>,
+
+    /// API URL for the reviewer (OpenAI-compatible, or e.g.
+    /// https://api.anthropic.com/v1 for the anthropic backend)
+    /// [default: http://localhost:11434/v1]
+    #[arg(long, global = true)]
+    reviewer_url: Option<String>,
 
-    /// Working directory for the task
-    #[arg(short, long, default_value = ".")]
-    working_dir: PathBuf,
+    /// Model for the reviewer [default: ollama/llama3.1]
+    #[arg(long, global = true)]
+    reviewer_model: Option<String>,
 
-    /// Model for the worker (e.g., "ollama/llama3.1")
-    #[arg(long, default_value = "ollama/llama3.1")]
-    worker_model: String,
+    /// File with the reviewer system prompt
+    #[arg(long, global = true)]
+    reviewer_system_prompt_file: Option<PathBuf>,
 
-    /// OpenAI-compatible API URL for reviewer
-    #[arg(long, default_value = "http://localhost:11434/v1")]
-    reviewer_url: String,
+    /// File with the review prompt template, using `{{task}}`, `{{sample}}`
+    /// and other placeholders
+    #[arg(long, global = true)]
+    reviewer_prompt_template: Option<PathBuf>,
 
-    /// Model for the reviewer
-    #[arg(long, default_value = "ollama/llama3.1")]
-    reviewer_model: String,
+    /// Limit reviewer prompts to about this many tokens, trimming the
+    /// sample and older summaries to fit
+    #[arg(long, global = true)]
+    reviewer_token_budget: Option<usize>,
 
-    /// Maximum iterations before forcing abort
-    #[arg(long, default_value = "10")]
-    max_iterations: usize,
+    /// Maximum iterations before forcing abort [default: 10]
+    #[arg(long, global = true)]
+    max_iterations: Option<usize>,
 
-    /// Inactivity timeout in seconds
-    #[arg(long, default_value = "30")]
-    inactivity_timeout: u64,
+    /// Inactivity timeout in seconds [default: 30]
+    #[arg(long, global = true)]
+    inactivity_timeout: Option<u64>,
+
+    /// Number of output lines sampled for each review [default: 100]
+    #[arg(long, global = true)]
+    sample_lines: Option<usize>,
 
     /// Run without TUI (headless mode)
-    #[arg(long)]
+    #[arg(long, global = true)]
     headless: bool,
 
+    /// Supervise an existing OpenCode session instead of creating one
+    #[arg(long, global = true)]
+    session_id: Option<String>,
+
+    /// URL of an already-running OpenCode server to use instead of spawning one
+    #[arg(long, global = true)]
+    server_url: Option<String>,
+
+    /// Directory where run state is saved for resuming [default: .opencode_runner/runs]
+    #[arg(long, global = true)]
+    runs_dir: Option<PathBuf>,
+
+    /// Write a JSON report of the finished run (or batch) to this path
+    #[arg(long, global = true)]
+    report: Option<PathBuf>,
+
+    /// Write a Markdown summary of the finished run to this path
+    #[arg(long, global = true)]
+    report_markdown: Option<PathBuf>,
+
+    /// Write a JUnit XML report of the finished run to this path
+    #[arg(long, global = true)]
+    report_junit: Option<PathBuf>,
+
+    /// Run each task in a fresh git worktree on its own branch, keeping the
+    /// branch when the task completes and discarding it otherwise
+    #[arg(long, global = true)]
+    worktree: bool,
+
+    /// Directory for run worktrees [default: .opencode_runner/worktrees]
+    #[arg(long, global = true)]
+    worktrees_dir: Option<PathBuf>,
+
+    /// Commit a snapshot of the working tree after every review and roll
+    /// back to the last approved one when the reviewer aborts
+    #[arg(long, global = true)]
+    checkpoints: bool,
+
+    /// Record every session event and review to events.jsonl in the run
+    /// directory, for the `replay` command
+    #[arg(long, global = true)]
+    record_events: bool,
+
+    /// Command that must pass before the task counts as completed, run in
+    /// the working directory when the worker appears done (repeatable)
+    #[arg(long = "verify", value_name = "COMMAND", global = true)]
+    verify: Vec<String>,
+
+    /// Seconds a verification command may run before it counts as failed [default: 600]
+    #[arg(long, global = true)]
+    verify_timeout: Option<u64>,
+
+    /// Number of batch tasks to run concurrently [default: 1]
+    #[arg(short, long, global = true)]
+    jobs: Option<usize>,
+
+    /// Maximum reviewer requests in flight across concurrent tasks [default: 1]
+    #[arg(long, global = true)]
+    reviewer_max_concurrent: Option<usize>,
+
+    /// Minimum milliseconds between reviewer requests across concurrent tasks [default: 0]
+    #[arg(long, global = true)]
+    reviewer_min_interval_ms: Option<u64>,
+
     /// Additional arguments passed to `opencode serve`
     #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
     extra_args: Vec<String>,
 }
 
+impl Args {
+    /// Settings given on the command line, the top configuration layer
+    fn config_layer(&self) -> ConfigLayer {
+        ConfigLayer {
+            task: self.task.clone(),
+            working_dir: self.working_dir.clone(),
+            worker_model: self.worker_model.clone(),
+            reviewer_backend: self.reviewer_backend,
+            reviewer_url: self.reviewer_url.clone(),
+            reviewer_model: self.reviewer_model.clone(),
+            reviewer_system_prompt: None,
+            reviewer_system_prompt_file: self.reviewer_system_prompt_file.clone(),
+            reviewer_prompt_template: self.reviewer_prompt_template.clone(),
+            reviewer_token_budget: self.reviewer_token_budget,
+            max_iterations: self.max_iterations,
+            inactivity_timeout: self.inactivity_timeout,
+            sample_lines: self.sample_lines,
+            headless: self.headless.then_some(true),
+            server_url: self.server_url.clone(),
+            runs_dir: self.runs_dir.clone(),
+            worktree: self.worktree.then_some(true),
+            worktrees_dir: self.worktrees_dir.clone(),
+            checkpoints: self.checkpoints.then_some(true),
+            record_events: self.record_events.then_some(true),
+            verify: (!self.verify.is_empty()).then(|| self.verify.clone()),
+            verify_timeout: self.verify_timeout,
+            jobs: self.jobs,
+            reviewer_max_concurrent: self.reviewer_max_concurrent,
+            reviewer_min_interval_ms: self.reviewer_min_interval_ms,
+        }
+    }
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Resume an interrupted run from its saved state
+    Resume {
+        /// Id of the run to resume
+        run_id: String,
+    },
+    /// Run each task in a YAML or JSONL file in its own session
+    Batch {
+        /// Batch file: a YAML list of tasks, or one JSON task per line (.jsonl)
+        file: PathBuf,
+    },
+    /// Run the reviewer over a recorded run and compare its decisions with
+    /// the recorded ones, without an OpenCode server
+    Replay {
+        /// Recording: an events.jsonl file, or the id of a run recorded
+        /// with --record-events
+        recording: PathBuf,
+    },
+    /// Measure how well the configured reviewer catches stuck workers on
+    /// recorded transcripts labelled with the correct outcome
+    Eval {
+        /// Directory of <name>.jsonl recordings and a labels.toml
+        dir: PathBuf,
+    },
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     // Initialize tracing
@@ -78,61 +257,44 @@ async fn main() -> Result<()> {
     let args = Args::parse();
 
     info!("Starting OpenCode Runner");
-    info!("Task: {}", args.task);
-    info!("Working directory: {}", args.working_dir.display());
-    info!("Worker model: {}", args.worker_model);
-    info!("Reviewer URL: {}", args.reviewer_url);
-    info!("Max iterations: {}", args.max_iterations);
-
-    // Spawn the OpenCode server
-    info!("Spawning OpenCode server...");
-    let server =
-        ServerManager::spawn(&args.working_dir, &args.worker_model, &args.extra_args).await?;
-
-    info!("Server spawned on port {}", server.port());
 
-    // Give the server a moment to fully initialize
-    tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
-
-    // Connect to the server
-    info!("Connecting to OpenCode server at {}...", server.base_url());
-    let client = OpenCodeClient::connect(server.base_url()).await?;
-    info!("Connected to OpenCode server");
-
-    // Create components
-    let reviewer = ReviewerClient::new(args.reviewer_url, args.reviewer_model);
-
-    let sampler = Sampler::new(100);
-    let state = State::new();
+    // One handler for the whole process, so signals between or outside runs
+    // are not lost; runs observe the token and wind down on their own
+    let shutdown = CancellationToken::new();
+    tokio::spawn(handle_signals(shutdown.clone()));
 
-    // Create control loop configuration
-    let config =
-        ControlConfig::from_args(&args.task, args.max_iterations, args.inactivity_timeout)?;
-
-    // Create control loop
-    let mut control_loop = ControlLoop::new(client, reviewer, sampler, state, config);
+    let result = match RunnerConfig::load(
+        args.config.as_deref(),
+        args.profile.as_deref(),
+        &args.config_layer(),
+    ) {
+        Ok(config) => match args.command {
+            Some(Command::Resume { ref run_id }) => {
+                resume_run(&args, &config, run_id, &shutdown).await
+            }
+            Some(Command::Batch { ref file }) => run_batch(&args, &config, file, &shutdown).await,
+            Some(Command::Replay { ref recording }) => {
+                unless_interrupted(&shutdown, replay_recording(&args, &config, recording)).await
+            }
+            Some(Command::Eval { ref dir }) => {
+                unless_interrupted(&shutdown, evaluate_reviewer(&args, &config, dir)).await
+            }
+            None => start_run(&args, &config, &shutdown).await,
+        },
+        Err(e) => Err(e),
+    };
 
-    // Run in TUI or headless mode
-    let result = if args.headless {
-        info!("Running in headless mode");
-        control_loop.run(None).await
-    } else {
-        #[cfg(feature = "tui")]
-        {
-            info!("Starting TUI");
-            run_tui_mode(control_loop).await
-        }
-        #[cfg(not(feature = "tui"))]
-        {
-            warn!("TUI feature not enabled, falling back to headless mode");
-            control_loop.run(None).await
+    // A signal outside a run (while connecting, between batch tasks or while
+    // shutting the server down) interrupts the command all the same
+    let result = if shutdown.is_cancelled() {
+        if let Err(ref e) = result {
+            warn!("{:#}", e);
         }
+        Ok(RunResult::Interrupted)
+    } else {
+        result
     };
 
-    // Cleanup
-    info!("Shutting down server...");
-    server.shutdown().await?;
-
     // Print result
     match result {
         Ok(RunResult::Completed) => {
@@ -147,6 +309,10 @@ async fn main() -> Result<()> {
             warn!("Task reached maximum iterations");
             std::process::exit(1);
         }
+        Ok(RunResult::Interrupted) => {
+            warn!("Task interrupted by user");
+            std::process::exit(EXIT_INTERRUPTED);
+        }
         Err(e) => {
             eprintln!("Error: {}", e);
             std::process::exit(1);
@@ -154,14 +320,799 @@ async fn main() -> Result<()> {
     }
 }
 
+/// Start a new run for the task given on the command line
+async fn start_run(
+    args: &Args,
+    settings: &RunnerConfig,
+    shutdown: &CancellationToken,
+) -> Result<RunResult> {
+    if settings.task.is_none() && args.session_id.is_none() {
+        anyhow::bail!("--task is required unless attaching to a session or resuming a run");
+    }
+    if settings.worktree && (settings.server_url.is_some() || args.session_id.is_some()) {
+        anyhow::bail!(
+            "--worktree needs its own server and session, not --server-url or --session-id"
+        );
+    }
+
+    info!("Working directory: {}", settings.working_dir.display());
+    info!("Worker model: {}", settings.worker_model);
+    info!("Reviewer backend: {:?}", settings.reviewer_backend);
+    info!("Reviewer URL: {}", settings.reviewer_url);
+    info!("Max iterations: {}", settings.max_iterations);
+
+    if settings.checkpoints {
+        git::repo_root(&settings.working_dir)
+            .await
+            .context("--checkpoints needs a git repository")?;
+    }
+
+    let run_id = generate_run_id();
+    let worktree = if settings.worktree {
+        Some(Worktree::create(&settings.working_dir, &settings.worktrees_dir, &run_id).await?)
+    } else {
+        None
+    };
+
+    let result = run_new_task(args, settings, run_id, worktree.clone(), shutdown).await;
+
+    if let Some(worktree) = worktree {
+        let task = settings.task.as_deref().unwrap_or_default();
+        finish_worktree(worktree, result.as_ref().ok(), task).await;
+    }
+
+    result
+}
+
+/// Connect to (or spawn) the server for a new run and run it to the end
+async fn run_new_task(
+    args: &Args,
+    settings: &RunnerConfig,
+    run_id: String,
+    worktree: Option<Worktree>,
+    shutdown: &CancellationToken,
+) -> Result<RunResult> {
+    let working_dir = match worktree {
+        Some(ref worktree) => worktree.map_dir(&settings.working_dir),
+        None => settings.working_dir.clone(),
+    };
+
+    let (server, client) = connect_server(
+        settings.server_url.as_deref(),
+        &working_dir,
+        &settings.worker_model,
+        &args.extra_args,
+        shutdown,
+    )
+    .await?;
+
+    // An attached session's task defaults to its first prompt
+    let task = match (&settings.task, &args.session_id) {
+        (Some(task), _) => task.clone(),
+        (None, Some(session_id)) => client
+            .session_history(session_id)
+            .await?
+            .first_prompt
+            .with_context(|| format!("Session {} has no prompt, pass --task", session_id))?,
+        (None, None) => unreachable!(),
+    };
+    info!("Task: {}", task);
+
+    // Create control loop configuration
+    let config = settings.control_config(&task)?;
+
+    let mut meta = new_run_meta(settings, run_id, server.base_url(), config);
+    meta.session_id = args.session_id.clone();
+    meta.working_dir = working_dir;
+    meta.worktree = worktree;
+
+    let store = RunStore::create(&settings.runs_dir, &meta.run_id)?;
+    info!(
+        "Run id: {} (state in {})",
+        meta.run_id,
+        store.dir().display()
+    );
+    let control_loop = build_control_loop(client, meta, store, State::new(), None, shutdown)?;
+
+    let outcome = execute(control_loop, settings.headless).await;
+
+    // Cleanup
+    info!("Shutting down server...");
+    server.shutdown().await?;
+
+    let (control_loop, result) = outcome?;
+    finish_run(args, &control_loop, &result)?;
+
+    Ok(result)
+}
+
+/// Run every task in a batch file, `jobs` at a time
+///
+/// Each task gets a new session, on one shared server or, with worktrees,
+/// on a server of its own. Returns `Completed` only if every task
+/// completed, so the exit code reflects the whole batch.
+async fn run_batch(
+    args: &Args,
+    settings: &RunnerConfig,
+    file: &Path,
+    shutdown: &CancellationToken,
+) -> Result<RunResult> {
+    let tasks = batch::load_tasks(file)?;
+    let jobs = settings.jobs.max(1);
+    info!(
+        "Loaded {} tasks from {}, running {} at a time",
+        tasks.len(),
+        file.display(),
+        jobs
+    );
+
+    if !settings.headless {
+        info!("Batch mode runs headless");
+    }
+    if settings.worktree && settings.server_url.is_some() {
+        anyhow::bail!("--worktree needs its own servers, not --server-url");
+    }
+    if jobs > 1 && !settings.worktree {
+        warn!("Concurrent tasks share the working directory and may conflict, consider --worktree");
+    }
+
+    // Without worktrees every task connects its own client to one shared server
+    let shared_server = if settings.worktree {
+        None
+    } else {
+        let (server, _) = connect_server(
+            settings.server_url.as_deref(),
+            &settings.working_dir,
+            &settings.worker_model,
+            &args.extra_args,
+            shutdown,
+        )
+        .await?;
+        Some(server)
+    };
+
+    // One limiter for all tasks so concurrent runs don't flood the reviewer
+    let limiter = Arc::new(RateLimiter::new(
+        settings.reviewer_max_concurrent,
+        Duration::from_millis(settings.reviewer_min_interval_ms),
+    ));
+    let batch_id = generate_run_id();
+    // Cancelled by a signal too, so no new tasks start after one
+    let stop = shutdown.child_token();
+
+    let outcomes: Vec<_> = futures::stream::iter(tasks.iter().enumerate())
+        .map(|(index, task)| {
+            let limiter = limiter.clone();
+            let stop = stop.clone();
+            let shared_server = shared_server.as_ref();
+            let batch_id = &batch_id;
+            let total = tasks.len();
+
+            async move {
+                // Don't start new tasks once the batch is interrupted
+                if stop.is_cancelled() {
+                    return None;
+                }
+                info!("Batch task {}/{}: {}", index + 1, total, task.task);
+
+                let run_id = format!("{}-{}", batch_id, index + 1);
+                let outcome = run_batch_task(
+                    args,
+                    settings,
+                    task,
+                    run_id,
+                    shared_server,
+                    limiter,
+                    shutdown,
+                )
+                .await;
+
+                match outcome {
+                    Ok((control_loop, result)) => {
+                        print_final_report(control_loop.state(), &result);
+                        if matches!(result, RunResult::Interrupted) {
+                            warn!("Batch interrupted, skipping remaining tasks");
+                            stop.cancel();
+                        }
+                        Some(Ok(run_report(&control_loop, &result)))
+                    }
+                    Err(e) => {
+                        warn!("Batch task {} failed: {:#}", index + 1, e);
+                        Some(Err(BatchError {
+                            task: task.task.clone(),
+                            error: format!("{:#}", e),
+                        }))
+                    }
+                }
+            }
+        })
+        .buffered(jobs)
+        .collect()
+        .await;
+
+    let mut runs = Vec::new();
+    let mut errors = Vec::new();
+    for outcome in outcomes.into_iter().flatten() {
+        match outcome {
+            Ok(report) => runs.push(report),
+            Err(error) => errors.push(error),
+        }
+    }
+
+    if let Some(server) = shared_server {
+        info!("Shutting down server...");
+        server.shutdown().await?;
+    }
+
+    let report = BatchReport::new(tasks.len(), runs, errors);
+    println!();
+    println!("=== OpenCode Runner batch report ===");
+    println!("{}", report.format_summary());
+
+    if let Some(ref path) = args.report {
+        report.write_json(path)?;
+        info!("Wrote batch report to {}", path.display());
+    }
+    if let Some(ref path) = args.report_markdown {
+        report.write_markdown(path)?;
+        info!("Wrote Markdown report to {}", path.display());
+    }
+    if let Some(ref path) = args.report_junit {
+        report.write_junit(path)?;
+        info!("Wrote JUnit report to {}", path.display());
+    }
+
+    Ok(if report.all_completed() {
+        RunResult::Completed
+    } else if report.interrupted > 0 {
+        RunResult::Interrupted
+    } else {
+        RunResult::Aborted(report.format_summary())
+    })
+}
+
+/// Run a single batch task, in its own worktree if enabled
+async fn run_batch_task(
+    args: &Args,
+    settings: &RunnerConfig,
+    task: &BatchTask,
+    run_id: String,
+    shared_server: Option<&ServerManager>,
+    limiter: Arc<RateLimiter>,
+    shutdown: &CancellationToken,
+) -> Result<(ControlLoop, RunResult)> {
+    let config = ControlConfig::from_args(
+        &task.task,
+        task.max_iterations.unwrap_or(settings.max_iterations),
+        task.inactivity_timeout
+            .unwrap_or(settings.inactivity_timeout),
+    )?;
+
+    let worktree = if settings.worktree {
+        Some(Worktree::create(&settings.working_dir, &settings.worktrees_dir, &run_id).await?)
+    } else {
+        None
+    };
+
+    let outcome = async {
+        let mut meta = new_run_meta(settings, run_id, "", config);
+        if let Some(ref verify) = task.verify {
+            meta.verify = verify.clone();
+        }
+        let store = RunStore::create(&settings.runs_dir, &meta.run_id)?;
+
+        match shared_server {
+            Some(server) => {
+                let client = OpenCodeClient::connect(server.base_url()).await?;
+                meta.server_url = server.base_url().to_string();
+                let control_loop =
+                    build_control_loop(client, meta, store, State::new(), Some(limiter), shutdown)?;
+                execute(control_loop, true).await
+            }
+            None => {
+                // Each worktree gets a server running inside it
+                if let Some(ref worktree) = worktree {
+                    meta.working_dir = worktree.map_dir(&settings.working_dir);
+                    meta.worktree = Some(worktree.clone());
+                }
+                let (server, client) = connect_server(
+                    None,
+                    &meta.working_dir,
+                    &settings.worker_model,
+                    &args.extra_args,
+                    shutdown,
+                )
+                .await?;
+                meta.server_url = server.base_url().to_string();
+                let control_loop =
+                    build_control_loop(client, meta, store, State::new(), Some(limiter), shutdown)?;
+                let outcome = execute(control_loop, true).await;
+                server.shutdown().await?;
+                outcome
+            }
+        }
+    }
+    .await;
+
+    if let Some(worktree) = worktree {
+        let result = outcome.as_ref().ok().map(|(_, result)| result);
+        finish_worktree(worktree, result, &task.task).await;
+    }
+
+    outcome
+}
+
+/// Settings for a new run, taken from the resolved configuration
+fn new_run_meta(
+    settings: &RunnerConfig,
+    run_id: String,
+    server_url: &str,
+    config: ControlConfig,
+) -> RunMeta {
+    RunMeta {
+        run_id,
+        session_id: None,
+        server_url: server_url.to_string(),
+        working_dir: settings.working_dir.clone(),
+        worker_model: settings.worker_model.clone(),
+        reviewer_backend: settings.reviewer_backend,
+        reviewer_url: settings.reviewer_url.clone(),
+        reviewer_model: settings.reviewer_model.clone(),
+        reviewer_system_prompt: settings.reviewer_system_prompt.clone(),
+        reviewer_system_prompt_file: settings.reviewer_system_prompt_file.clone(),
+        reviewer_prompt_template: settings.reviewer_prompt_template.clone(),
+        reviewer_token_budget: settings.reviewer_token_budget,
+        sample_lines: settings.sample_lines,
+        config,
+        worktree: None,
+        checkpoints: settings.checkpoints,
+        record_events: settings.record_events,
+        verify: settings.verify.clone(),
+        verify_timeout: settings.verify_timeout,
+        created_at: Utc::now(),
+    }
+}
+
+/// Build the control loop for a run from its settings
+///
+/// Each run gets its own reviewer (the heuristic backend keeps per-run
+/// history); `limiter` is shared between concurrent runs. The run is
+/// interrupted when `shutdown` is cancelled.
+fn build_control_loop(
+    client: OpenCodeClient,
+    meta: RunMeta,
+    store: RunStore,
+    state: State,
+    limiter: Option<Arc<RateLimiter>>,
+    shutdown: &CancellationToken,
+) -> Result<ControlLoop> {
+    let options = ReviewerOptions::load(
+        meta.reviewer_system_prompt.as_deref(),
+        meta.reviewer_system_prompt_file.as_deref(),
+        meta.reviewer_prompt_template.as_deref(),
+        meta.reviewer_token_budget,
+    )?;
+    let mut reviewer = meta.reviewer_backend.build_with_options(
+        meta.reviewer_url.clone(),
+        meta.reviewer_model.clone(),
+        options,
+    )?;
+    if let Some(limiter) = limiter {
+        reviewer = Arc::new(RateLimitedReviewer::new(reviewer, limiter));
+    }
+
+    let sampler = Sampler::new(meta.sample_lines);
+    let config = meta.config.clone();
+
+    let diff_tracker = DiffTracker::new(meta.working_dir.clone(), &meta.run_id);
+    let mut control_loop = ControlLoop::new(client, reviewer, sampler, state, config)
+        .with_diff_tracker(diff_tracker)
+        .with_shutdown(shutdown);
+    if let Some(ref session_id) = meta.session_id {
+        control_loop = control_loop.with_session(session_id.clone());
+    }
+    if meta.checkpoints {
+        let checkpoints = Checkpoints::new(meta.working_dir.clone(), &meta.run_id);
+        info!("Checkpointing working tree to {}", checkpoints.ref_name());
+        control_loop = control_loop.with_checkpoints(checkpoints);
+    }
+    if !meta.verify.is_empty() {
+        let verifier = Verifier::new(meta.working_dir.clone(), meta.verify.clone())
+            .with_timeout(Duration::from_secs(meta.verify_timeout));
+        info!(
+            "Verifying completion with: {}",
+            verifier.commands().join(", ")
+        );
+        control_loop = control_loop.with_verifier(verifier);
+    }
+    if meta.record_events {
+        let path = store.events_path();
+        info!("Recording session events to {}", path.display());
+        let recorder = EventRecorder::open(&path, &meta.config.task)?;
+        control_loop = control_loop.with_recorder(recorder);
+    }
+    Ok(control_loop.with_run_store(store, meta))
+}
+
+/// Keep or throw away a run's worktree depending on how the run ended
+///
+/// Completed runs are committed to the worktree's branch, interrupted runs
+/// are left in place so they can be resumed, anything else is discarded.
+async fn finish_worktree(worktree: Worktree, result: Option<&RunResult>, task: &str) {
+    let cleanup = match result {
+        Some(RunResult::Completed) => {
+            let summary = task.lines().next().unwrap_or_default();
+            worktree
+                .keep(&format!("opencode_runner: {}", summary))
+                .await
+        }
+        Some(RunResult::Interrupted) => {
+            info!(
+                "Leaving worktree {} in place for resume",
+                worktree.path.display()
+            );
+            Ok(())
+        }
+        _ => worktree.discard().await,
+    };
+
+    if let Err(e) = cleanup {
+        warn!("Failed to clean up worktree: {:#}", e);
+    }
+}
+
+/// Resume a saved run, reattaching to its OpenCode session
+async fn resume_run(
+    args: &Args,
+    settings: &RunnerConfig,
+    run_id: &str,
+    shutdown: &CancellationToken,
+) -> Result<RunResult> {
+    let store = RunStore::open(&settings.runs_dir, run_id)?;
+    let (mut meta, state) = store.load()?;
+    let session_id = meta
+        .session_id
+        .clone()
+        .context("Saved run has no session to resume")?;
+
+    info!(
+        "Resuming run {} (session {}) after iteration {}/{}",
+        meta.run_id,
+        session_id,
+        state.current_iteration(),
+        meta.config.max_iterations
+    );
+
+    // Reuse the recorded server if it is still up, otherwise start a new one
+    // in the same working directory so it can see the saved session
+    let server_url = match settings.server_url {
+        Some(ref url) => Some(url.clone()),
+        None => match OpenCodeClient::connect(&meta.server_url).await {
+            Ok(client) if client.health_check().await.is_ok() => Some(meta.server_url.clone()),
+            _ => None,
+        },
+    };
+
+    let (server, client) = connect_server(
+        server_url.as_deref(),
+        &meta.working_dir,
+        &meta.worker_model,
+        &args.extra_args,
+        shutdown,
+    )
+    .await?;
+    meta.server_url = server.base_url().to_string();
+
+    let worktree = meta.worktree.clone();
+    let task = meta.config.task.clone();
+    let control_loop =
+        build_control_loop(client, meta, store, state, None, shutdown)?.with_resume();
+
+    let outcome = execute(control_loop, settings.headless).await;
+
+    info!("Shutting down server...");
+    server.shutdown().await?;
+
+    let result = match outcome {
+        Ok((control_loop, result)) => finish_run(args, &control_loop, &result).map(|()| result),
+        Err(e) => Err(e),
+    };
+
+    if let Some(worktree) = worktree {
+        finish_worktree(worktree, result.as_ref().ok(), &task).await;
+    }
+
+    result
+}
+
+/// Replay a recorded run through the configured reviewer
+///
+/// The reviewer settings (backend, model, prompts, budget, sample size)
+/// come from the current configuration, so variants can be compared on the
+/// same transcript.
+async fn replay_recording(
+    args: &Args,
+    settings: &RunnerConfig,
+    source: &Path,
+) -> Result<RunResult> {
+    let path = if source.is_file() {
+        source.to_path_buf()
+    } else {
+        RunStore::open(&settings.runs_dir, &source.to_string_lossy())?.events_path()
+    };
+    let entries = recording::load_recording(&path)?;
+
+    let reviewer = settings.reviewer_backend.build_with_options(
+        settings.reviewer_url.clone(),
+        settings.reviewer_model.clone(),
+        settings.reviewer_options()?,
+    )?;
+    info!(
+        "Replaying {} with the {} reviewer",
+        path.display(),
+        reviewer.name()
+    );
+
+    let steps = recording::replay(&entries, reviewer.as_ref(), settings.sample_lines).await?;
+    if steps.is_empty() {
+        warn!("Recording has no reviews to replay");
+    }
+    let report = ReplayReport::new(steps);
+
+    println!();
+    println!("=== Replay of {} ===", path.display());
+    for step in &report.steps {
+        let marker = if step.matches() { "" } else { " (changed)" };
+        println!(
+            "Iter {}: {:?} -> {:?}{} - {}",
+            step.iteration,
+            step.recorded.action,
+            step.replayed.action,
+            marker,
+            step.replayed.reason
+        );
+    }
+    println!("{}", report.format_summary());
+
+    if let Some(ref path) = args.report {
+        report.write_json(path)?;
+        info!("Wrote replay report to {}", path.display());
+    }
+
+    Ok(RunResult::Completed)
+}
+
+/// Score the configured reviewer against a directory of labelled recordings
+async fn evaluate_reviewer(args: &Args, settings: &RunnerConfig, dir: &Path) -> Result<RunResult> {
+    let options = settings.reviewer_options()?;
+    let make_reviewer = || {
+        settings.reviewer_backend.build_with_options(
+            settings.reviewer_url.clone(),
+            settings.reviewer_model.clone(),
+            options.clone(),
+        )
+    };
+    info!(
+        "Evaluating the {} reviewer ({}) on {}",
+        settings.reviewer_backend.name(),
+        settings.reviewer_model,
+        dir.display()
+    );
+
+    let report = eval::evaluate(dir, make_reviewer, settings.sample_lines).await?;
+
+    println!();
+    println!("=== Reviewer evaluation of {} ===", dir.display());
+    for result in &report.transcripts {
+        let aborted = match result.aborted_at {
+            Some(iteration) => format!("aborted at iteration {}", iteration),
+            None => "never aborted".to_string(),
+        };
+        println!(
+            "{}: {:?} ({}, {} reviews)",
+            result.name, result.outcome, aborted, result.reviews
+        );
+    }
+    println!("{}", report.format_summary());
+
+    if let Some(ref path) = args.report {
+        report.write_json(path)?;
+        info!("Wrote evaluation report to {}", path.display());
+    }
+
+    Ok(RunResult::Completed)
+}
+
+/// Attach to `server_url` if given, otherwise spawn a server in `working_dir`
+///
+/// Gives up if `shutdown` is cancelled first; a half-started server is
+/// killed when it is dropped.
+async fn connect_server(
+    server_url: Option<&str>,
+    working_dir: &Path,
+    worker_model: &str,
+    extra_args: &[String],
+    shutdown: &CancellationToken,
+) -> Result<(ServerManager, OpenCodeClient)> {
+    let connect = async {
+        let server = match server_url {
+            Some(url) => {
+                info!("Attaching to OpenCode server at {}...", url);
+                warn!("Worker model and extra arguments are set by the attached server");
+                ServerManager::attach(url)?
+            }
+            None => {
+                // Spawn the OpenCode server
+                info!("Spawning OpenCode server...");
+                let server = ServerManager::spawn(working_dir, worker_model, extra_args).await?;
+
+                info!("Server spawned on port {}", server.port());
+
+                // Give the server a moment to fully initialize
+                tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+
+                server
+            }
+        };
+
+        // Connect to the server
+        info!("Connecting to OpenCode server at {}...", server.base_url());
+        let client = OpenCodeClient::connect(server.base_url()).await?;
+
+        // A spawned server was already health-checked while starting up
+        if !server.is_owned() {
+            client.health_check().await.with_context(|| {
+                format!("OpenCode server at {} is not healthy", server.base_url())
+            })?;
+        }
+
+        info!("Connected to OpenCode server");
+
+        Ok::<_, anyhow::Error>((server, client))
+    };
+
+    unless_interrupted(shutdown, connect).await
+}
+
+/// Run `future`, giving up with an error if `shutdown` is cancelled first
+async fn unless_interrupted<T>(
+    shutdown: &CancellationToken,
+    future: impl std::future::Future<Output = Result<T>>,
+) -> Result<T> {
+    tokio::select! {
+        result = future => result,
+        _ = shutdown.cancelled() => anyhow::bail!("Interrupted"),
+    }
+}
+
+/// Cancel `shutdown` on the first SIGINT/SIGTERM so the command can wind
+/// down, and exit straight away on the second
+async fn handle_signals(shutdown: CancellationToken) {
+    let signal = shutdown_signal().await;
+    warn!("Received {}, interrupting", signal);
+    shutdown.cancel();
+
+    let signal = shutdown_signal().await;
+    warn!("Received {} again, exiting", signal);
+    std::process::exit(EXIT_INTERRUPTED);
+}
+
+/// Wait for SIGINT (Ctrl-C) or, on Unix, SIGTERM
+async fn shutdown_signal() -> &'static str {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => tokio::select! {
+                _ = tokio::signal::ctrl_c() => "SIGINT",
+                _ = sigterm.recv() => "SIGTERM",
+            },
+            Err(e) => {
+                warn!("Could not install SIGTERM handler: {}", e);
+                let _ = tokio::signal::ctrl_c().await;
+                "SIGINT"
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+        "SIGINT"
+    }
+}
+
+/// Run the control loop in TUI or headless mode
+///
+/// Hands the control loop back so its final state can be reported.
+async fn execute(
+    mut control_loop: ControlLoop,
+    headless: bool,
+) -> Result<(ControlLoop, RunResult)> {
+    if headless {
+        info!("Running in headless mode");
+        let result = control_loop.run(None).await?;
+        Ok((control_loop, result))
+    } else {
+        #[cfg(feature = "tui")]
+        {
+            info!("Starting TUI");
+            run_tui_mode(control_loop).await
+        }
+        #[cfg(not(feature = "tui"))]
+        {
+            warn!("TUI feature not enabled, falling back to headless mode");
+            let result = control_loop.run(None).await?;
+            Ok((control_loop, result))
+        }
+    }
+}
+
+/// Print the final report and write any requested report files
+fn finish_run(args: &Args, control_loop: &ControlLoop, result: &RunResult) -> Result<()> {
+    print_final_report(control_loop.state(), result);
+
+    let report = run_report(control_loop, result);
+
+    if let Some(ref path) = args.report {
+        report.write_json(path)?;
+        info!("Wrote run report to {}", path.display());
+    }
+    if let Some(ref path) = args.report_markdown {
+        report.write_markdown(path)?;
+        info!("Wrote Markdown report to {}", path.display());
+    }
+    if let Some(ref path) = args.report_junit {
+        report.write_junit(path)?;
+        info!("Wrote JUnit report to {}", path.display());
+    }
+
+    Ok(())
+}
+
+/// Build the machine-readable report of a finished run
+fn run_report(control_loop: &ControlLoop, result: &RunResult) -> RunReport {
+    RunReport::new(
+        control_loop.config(),
+        control_loop.session_id(),
+        control_loop.state(),
+        result,
+    )
+    .with_run_id(control_loop.run_id())
+    .with_settings(control_loop.run_meta().map(RunSettings::from_meta))
+}
+
+/// Print a human-readable summary of the finished run
+fn print_final_report(state: &State, result: &RunResult) {
+    println!();
+    println!("=== OpenCode Runner report ===");
+    println!("Result: {}", result);
+    println!(
+        "Iterations: {} | Runtime: {}s | Lines sampled: {} | Reviewer retries: {}",
+        state.current_iteration(),
+        state.runtime().num_seconds(),
+        state.total_lines_sampled(),
+        state.total_retries()
+    );
+    let usage = state.total_usage();
+    if usage.total_tokens() > 0 {
+        println!(
+            "Tokens: {} in / {} out | Cost: ${:.4}",
+            usage.input, usage.output, usage.cost
+        );
+    }
+    println!("{}", state.format_activity_log());
+}
+
 #[cfg(feature = "tui")]
-async fn run_tui_mode(mut control_loop: ControlLoop) -> Result<RunResult> {
+async fn run_tui_mode(mut control_loop: ControlLoop) -> Result<(ControlLoop, RunResult)> {
     use control_loop::UiEvent;
     use std::sync::Arc;
     use tokio::sync::{mpsc, Mutex};
 
-    let (event_sender, mut event_receiver) = mpsc::channel(100);
+    let (event_sender, mut event_receiver) = mpsc::channel::<UiEvent>(100);
     let ui_state = Arc::new(Mutex::new(tui::UiState::new()));
+    let cancel = control_loop.cancellation_token();
 
     // Run control loop
     let control_handle = {
@@ -185,10 +1136,14 @@ async fn run_tui_mode(mut control_loop: ControlLoop) -> Result<RunResult> {
                         state.set_status("Max iterations reached".to_string());
                         state.set_completed(Some("Max iterations reached".to_string()));
                     }
+                    RunResult::Interrupted => {
+                        state.set_status("Interrupted".to_string());
+                        state.set_completed(Some("Interrupted by user".to_string()));
+                    }
                 }
             }
 
-            result
+            result.map(|run_result| (control_loop, run_result))
         })
     };
 
@@ -213,6 +1168,10 @@ async fn run_tui_mode(mut control_loop: ControlLoop) -> Result<RunResult> {
                 tui.run(ui_state).await
             };
 
+            // Raw mode swallows Ctrl-C, so quitting the TUI interrupts the run
+            // (no-op if the control loop already finished)
+            cancel.cancel();
+
             // Wait for event processor to finish
             let _ = event_processor.await;
 
@@ -221,7 +1180,7 @@ async fn run_tui_mode(mut control_loop: ControlLoop) -> Result<RunResult> {
     };
 
     // Wait for control loop to finish
-    let result = control_handle.await??;
+    let outcome = control_handle.await??;
 
     // Give TUI a moment to render final state
     tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
@@ -229,5 +1188,5 @@ async fn run_tui_mode(mut control_loop: ControlLoop) -> Result<RunResult> {
     // Cleanup
     let _ = ui_handle.await;
 
-    Ok(result)
+    Ok(outcome)
 }
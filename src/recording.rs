@@ -0,0 +1,299 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use opencode_rs::types::event::Event;
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::time::Instant;
+use tracing::{info, warn};
+
+use crate::{
+    git::TreeDiff,
+    report::write_report,
+    reviewer::{Reviewer, ReviewerAction, ReviewerContext, ReviewerDecision},
+    sampler::Sampler,
+    state::State,
+    verify::VerificationResult,
+};
+
+/// One line of an event recording
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RecordEntry {
+    /// The control loop started (or resumed) supervising the task
+    Start { at: DateTime<Utc>, task: String },
+    /// Event received from the session's event stream, kept as JSON so
+    /// recordings outlive changes to the event types
+    Event {
+        at: DateTime<Utc>,
+        event: serde_json::Value,
+    },
+    /// Lines recovered from the session's message history
+    History {
+        at: DateTime<Utc>,
+        lines: Vec<String>,
+    },
+    /// The reviewer was asked for a decision about the lines sampled so far
+    Review {
+        at: DateTime<Utc>,
+        iteration: usize,
+        worker_idle: bool,
+        #[serde(default)]
+        verification: Vec<VerificationResult>,
+        #[serde(default)]
+        diff: Option<TreeDiff>,
+        decision: ReviewerDecision,
+    },
+}
+
+/// Appends everything the control loop sees to a JSONL file
+pub struct EventRecorder {
+    writer: BufWriter<File>,
+}
+
+impl EventRecorder {
+    /// Open `path` for appending, so a resumed run continues its recording
+    pub fn open(path: &Path, task: &str) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open event recording {}", path.display()))?;
+
+        let mut recorder = Self {
+            writer: BufWriter::new(file),
+        };
+        recorder.write(&RecordEntry::Start {
+            at: Utc::now(),
+            task: task.to_string(),
+        })?;
+        Ok(recorder)
+    }
+
+    /// Record an event from the session's event stream
+    pub fn record_event(&mut self, event: &Event) -> Result<()> {
+        let event = serde_json::to_value(event).context("Failed to serialize event")?;
+        self.write(&RecordEntry::Event {
+            at: Utc::now(),
+            event,
+        })
+    }
+
+    /// Record lines the sampler took from the session's message history
+    pub fn record_history(&mut self, lines: &[String]) -> Result<()> {
+        self.write(&RecordEntry::History {
+            at: Utc::now(),
+            lines: lines.to_vec(),
+        })
+    }
+
+    /// Record the review of `context` and the decision it produced
+    pub fn record_review(
+        &mut self,
+        context: &ReviewerContext,
+        decision: &ReviewerDecision,
+    ) -> Result<()> {
+        self.write(&RecordEntry::Review {
+            at: Utc::now(),
+            iteration: context.iteration,
+            worker_idle: context.worker_idle,
+            verification: context.verification.clone(),
+            diff: context.diff.clone(),
+            decision: decision.clone(),
+        })
+    }
+
+    /// Write one entry, flushed so the recording survives a crash
+    fn write(&mut self, entry: &RecordEntry) -> Result<()> {
+        serde_json::to_writer(&mut self.writer, entry)
+            .context("Failed to serialize recorded entry")?;
+        self.writer.write_all(b"\n")?;
+        self.writer
+            .flush()
+            .context("Failed to write event recording")
+    }
+}
+
+/// Read a recording written by `EventRecorder`
+pub fn load_recording(path: &Path) -> Result<Vec<RecordEntry>> {
+    let file = File::open(path)
+        .with_context(|| format!("Failed to open event recording {}", path.display()))?;
+
+    let mut entries = Vec::new();
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.with_context(|| format!("Failed to read {}", path.display()))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry = serde_json::from_str(&line).with_context(|| {
+            format!("Invalid entry on line {} of {}", index + 1, path.display())
+        })?;
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+/// A recorded review and what the reviewer decides when it is replayed
+#[derive(Debug, Clone, Serialize)]
+pub struct ReplayStep {
+    pub iteration: usize,
+    /// Lines in the sample given to the reviewer
+    pub sample_lines: usize,
+    /// Decision made during the recorded run
+    pub recorded: ReviewerDecision,
+    /// Decision made now, on the same sample
+    pub replayed: ReviewerDecision,
+    /// How long the replayed decision took, including retries
+    pub latency_ms: u64,
+}
+
+impl ReplayStep {
+    /// Whether the replayed decision took the same action as the recorded one
+    pub fn matches(&self) -> bool {
+        self.replayed.action == self.recorded.action
+    }
+}
+
+/// Outcome of replaying a recording
+#[derive(Debug, Clone, Serialize)]
+pub struct ReplayReport {
+    /// Reviews whose replayed action matched the recorded one
+    pub matched: usize,
+    pub steps: Vec<ReplayStep>,
+}
+
+impl ReplayReport {
+    pub fn new(steps: Vec<ReplayStep>) -> Self {
+        Self {
+            matched: steps.iter().filter(|step| step.matches()).count(),
+            steps,
+        }
+    }
+
+    /// One-line summary, e.g. "3 of 4 decisions unchanged"
+    pub fn format_summary(&self) -> String {
+        format!(
+            "{} of {} decisions unchanged",
+            self.matched,
+            self.steps.len()
+        )
+    }
+
+    /// Write the report as pretty-printed JSON
+    pub fn write_json(&self, path: &Path) -> Result<()> {
+        let json =
+            serde_json::to_string_pretty(self).context("Failed to serialize replay report")?;
+        write_report(path, &json)
+    }
+}
+
+/// Feed a recording through a sampler and `reviewer`, asking for a decision
+/// wherever the recorded run asked for one
+///
+/// The sampler is reset whenever the recording (re)starts and after every
+/// review, as the control loop does. Previous summaries come from the
+/// recorded decisions, since those are what steered the worker.
+pub async fn replay(
+    entries: &[RecordEntry],
+    reviewer: &dyn Reviewer,
+    sample_lines: usize,
+) -> Result<Vec<ReplayStep>> {
+    replay_steps(entries, reviewer, sample_lines, false).await
+}
+
+/// Like `replay`, but stop after the first replayed Abort, where a live
+/// run would have ended
+pub async fn replay_until_abort(
+    entries: &[RecordEntry],
+    reviewer: &dyn Reviewer,
+    sample_lines: usize,
+) -> Result<Vec<ReplayStep>> {
+    replay_steps(entries, reviewer, sample_lines, true).await
+}
+
+async fn replay_steps(
+    entries: &[RecordEntry],
+    reviewer: &dyn Reviewer,
+    sample_lines: usize,
+    stop_on_abort: bool,
+) -> Result<Vec<ReplayStep>> {
+    let mut sampler = Sampler::new(sample_lines);
+    let mut state = State::new();
+    let mut task = String::new();
+    let mut steps = Vec::new();
+
+    for entry in entries {
+        match entry {
+            RecordEntry::Start { task: started, .. } => {
+                task = started.clone();
+                sampler.clear();
+            }
+            RecordEntry::Event { event, .. } => {
+                match serde_json::from_value::<Event>(event.clone()) {
+                    Ok(event) => sampler.process_event(&event),
+                    Err(e) => warn!("Skipping unreadable recorded event: {}", e),
+                }
+            }
+            RecordEntry::History { lines, .. } => {
+                sampler.backfill(lines);
+            }
+            RecordEntry::Review {
+                iteration,
+                worker_idle,
+                verification,
+                diff,
+                decision,
+                ..
+            } => {
+                let context = ReviewerContext {
+                    task_description: task.clone(),
+                    iteration: *iteration,
+                    previous_summaries: state.get_previous_summaries(5),
+                    current_sample: sampler.sample(),
+                    worker_idle: *worker_idle,
+                    verification: verification.clone(),
+                    diff: diff.clone(),
+                };
+                let start = Instant::now();
+                let replayed = reviewer.review_with_retry(&context).await?.decision;
+
+                let step = ReplayStep {
+                    iteration: *iteration,
+                    sample_lines: sampler.line_count(),
+                    recorded: decision.clone(),
+                    replayed,
+                    latency_ms: start.elapsed().as_millis() as u64,
+                };
+                if step.matches() {
+                    info!(
+                        "Iteration {}: {:?} (unchanged)",
+                        step.iteration, step.replayed.action
+                    );
+                } else {
+                    warn!(
+                        "Iteration {}: recorded {:?}, replayed {:?} - {}",
+                        step.iteration,
+                        step.recorded.action,
+                        step.replayed.action,
+                        step.replayed.reason
+                    );
+                }
+
+                // Iterations without output are skipped without a review
+                while state.current_iteration() < *iteration {
+                    state.start_iteration();
+                }
+                state.record_decision(step.sample_lines, decision.clone(), 0);
+                let aborted = step.replayed.action == ReviewerAction::Abort;
+                steps.push(step);
+                if stop_on_abort && aborted {
+                    break;
+                }
+                sampler.clear();
+            }
+        }
+    }
+
+    Ok(steps)
+}
@@ -0,0 +1,363 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::{
+    batch::BatchError,
+    config::ControlConfig,
+    control_loop::RunResult,
+    reviewer::ReviewerBackend,
+    run_store::RunMeta,
+    state::{action_label, Iteration, State, TokenUsage},
+};
+
+/// Worker and reviewer settings a run was started with
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunSettings {
+    /// Working directory of the worker
+    pub working_dir: PathBuf,
+    /// Model used by the worker
+    pub worker_model: String,
+    pub reviewer_backend: ReviewerBackend,
+    pub reviewer_model: String,
+    pub reviewer_url: String,
+    /// Number of output lines sampled for each review
+    pub sample_lines: usize,
+}
+
+impl RunSettings {
+    pub fn from_meta(meta: &RunMeta) -> Self {
+        Self {
+            working_dir: meta.working_dir.clone(),
+            worker_model: meta.worker_model.clone(),
+            reviewer_backend: meta.reviewer_backend,
+            reviewer_model: meta.reviewer_model.clone(),
+            reviewer_url: meta.reviewer_url.clone(),
+            sample_lines: meta.sample_lines,
+        }
+    }
+}
+
+/// Machine-readable summary of a finished run
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RunReport {
+    /// Id of the saved run, if it was saved
+    pub run_id: Option<String>,
+    /// Task given to the worker
+    pub task: String,
+    /// Control loop configuration
+    pub config: ControlConfig,
+    /// Worker and reviewer settings, if the run was saved
+    #[serde(default)]
+    pub settings: Option<RunSettings>,
+    /// OpenCode session the worker ran in
+    pub session_id: Option<String>,
+    /// Final result: `completed`, `aborted`, `max_iterations` or `interrupted`
+    pub result: String,
+    /// Reason given by the reviewer when the run was aborted
+    pub reason: Option<String>,
+    /// When the run started
+    pub started_at: DateTime<Utc>,
+    /// When the report was generated
+    pub finished_at: DateTime<Utc>,
+    /// Total runtime in seconds
+    pub runtime_secs: i64,
+    /// Every reviewed iteration
+    pub iterations: Vec<Iteration>,
+    /// Worker token and cost totals
+    pub usage: TokenUsage,
+    /// Human-readable activity log
+    #[serde(default)]
+    pub activity_log: String,
+}
+
+impl RunReport {
+    /// Build a report from the final state of a run
+    pub fn new(
+        config: &ControlConfig,
+        session_id: Option<&str>,
+        state: &State,
+        result: &RunResult,
+    ) -> Self {
+        let reason = match result {
+            RunResult::Aborted(reason) => Some(reason.clone()),
+            _ => None,
+        };
+
+        Self {
+            run_id: None,
+            task: config.task.clone(),
+            config: config.clone(),
+            settings: None,
+            session_id: session_id.map(|s| s.to_string()),
+            result: result_label(result).to_string(),
+            reason,
+            started_at: state.start_time(),
+            finished_at: Utc::now(),
+            runtime_secs: state.runtime().num_seconds(),
+            iterations: state.iterations().to_vec(),
+            usage: state.total_usage(),
+            activity_log: state.format_activity_log(),
+        }
+    }
+
+    /// Set the id of the saved run
+    pub fn with_run_id(mut self, run_id: Option<&str>) -> Self {
+        self.run_id = run_id.map(|s| s.to_string());
+        self
+    }
+
+    /// Set the worker and reviewer settings of the run
+    pub fn with_settings(mut self, settings: Option<RunSettings>) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    /// Whether the run completed successfully
+    pub fn passed(&self) -> bool {
+        self.result == result_label(&RunResult::Completed)
+    }
+
+    /// Render the report as pretty-printed JSON
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self).context("Failed to serialize run report")
+    }
+
+    /// Write the report as JSON to `path`
+    pub fn write_json(&self, path: &Path) -> Result<()> {
+        write_report(path, &self.to_json()?)
+    }
+
+    /// Render the report as a Markdown summary with an iteration table
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        let status = match self.result.as_str() {
+            "completed" => "✅ Completed",
+            "aborted" => "❌ Aborted",
+            "max_iterations" => "⚠️ Max iterations reached",
+            _ => "⏹️ Interrupted",
+        };
+
+        out.push_str(&format!("## OpenCode Runner: {}\n\n", status));
+        out.push_str(&format!("**Task:** {}\n\n", markdown_cell(&self.task)));
+        if let Some(ref reason) = self.reason {
+            out.push_str(&format!("**Reason:** {}\n\n", markdown_cell(reason)));
+        }
+
+        out.push_str("| | |\n|---|---|\n");
+        if let Some(ref run_id) = self.run_id {
+            out.push_str(&format!("| Run | `{}` |\n", run_id));
+        }
+        if let Some(ref session_id) = self.session_id {
+            out.push_str(&format!("| Session | `{}` |\n", session_id));
+        }
+        out.push_str(&format!(
+            "| Iterations | {} / {} |\n",
+            self.iterations.len(),
+            self.config.max_iterations
+        ));
+        if let Some(ref settings) = self.settings {
+            out.push_str(&format!("| Worker | `{}` |\n", settings.worker_model));
+            out.push_str(&format!(
+                "| Reviewer | {} `{}` |\n",
+                settings.reviewer_backend.name(),
+                settings.reviewer_model
+            ));
+        }
+        out.push_str(&format!("| Runtime | {}s |\n", self.runtime_secs));
+        out.push_str(&format!(
+            "| Tokens | {} in / {} out |\n",
+            self.usage.input, self.usage.output
+        ));
+        out.push_str(&format!("| Cost | ${:.4} |\n", self.usage.cost));
+
+        if !self.iterations.is_empty() {
+            out.push_str("\n### Iterations\n\n");
+            out.push_str("| # | Time | Decision | Reason | Lines | Retries |\n");
+            out.push_str("|---|---|---|---|---|---|\n");
+            for iter in &self.iterations {
+                out.push_str(&format!(
+                    "| {} | {} | {} | {} | {} | {} |\n",
+                    iter.number,
+                    iter.timestamp.format("%H:%M:%S"),
+                    action_label(&iter.decision.action),
+                    markdown_cell(&iter.decision.reason),
+                    iter.sample_size,
+                    iter.reviewer_retry_count
+                ));
+            }
+        }
+
+        let verified: Vec<&Iteration> = self
+            .iterations
+            .iter()
+            .filter(|iter| !iter.verification.is_empty())
+            .collect();
+        if !verified.is_empty() {
+            out.push_str("\n### Verification\n\n");
+            for iter in verified {
+                for result in &iter.verification {
+                    out.push_str(&format!(
+                        "- Iteration {}: {} in {:.1}s\n",
+                        iter.number,
+                        result.summary(),
+                        result.duration_ms as f64 / 1000.0
+                    ));
+                }
+            }
+        }
+
+        if !self.activity_log.is_empty() {
+            out.push_str("\n<details><summary>Activity log</summary>\n\n```\n");
+            out.push_str(&self.activity_log);
+            out.push_str("\n```\n\n</details>\n");
+        }
+
+        out
+    }
+
+    /// Write the report as Markdown to `path`
+    pub fn write_markdown(&self, path: &Path) -> Result<()> {
+        write_report(path, &self.to_markdown())
+    }
+
+    /// Render the run as a JUnit `<testcase>` element
+    ///
+    /// Aborted runs and runs that hit the iteration limit are failures with
+    /// the reviewer's reason as the message; interrupted runs are skipped.
+    pub fn to_junit_testcase(&self) -> String {
+        let mut out = format!(
+            "  <testcase classname=\"opencode_runner\" name=\"{}\" time=\"{}\">\n",
+            xml_escape(&junit_name(&self.task)),
+            self.runtime_secs
+        );
+
+        match self.result.as_str() {
+            "completed" => {}
+            "interrupted" => out.push_str("    <skipped message=\"Interrupted by user\"/>\n"),
+            result => {
+                let message = match self.reason {
+                    Some(ref reason) => reason.as_str(),
+                    None => "Max iterations reached",
+                };
+                out.push_str(&format!(
+                    "    <failure type=\"{}\" message=\"{}\">{}</failure>\n",
+                    result,
+                    xml_escape(message),
+                    xml_escape(&self.activity_log)
+                ));
+            }
+        }
+
+        let mut system_out = format!("Task: {}\n", self.task);
+        if let Some(ref run_id) = self.run_id {
+            system_out.push_str(&format!("Run: {}\n", run_id));
+        }
+        if let Some(ref session_id) = self.session_id {
+            system_out.push_str(&format!("Session: {}\n", session_id));
+        }
+        out.push_str(&format!(
+            "    <system-out>{}</system-out>\n",
+            xml_escape(&system_out)
+        ));
+        out.push_str("  </testcase>\n");
+        out
+    }
+
+    /// Write the report as a single-test JUnit XML file to `path`
+    pub fn write_junit(&self, path: &Path) -> Result<()> {
+        write_report(path, &junit_xml(std::slice::from_ref(self), &[]))
+    }
+}
+
+/// Render reports as a JUnit XML test suite, one test case per run and
+/// an errored test case per task that failed before producing a report
+pub fn junit_xml(reports: &[RunReport], errors: &[BatchError]) -> String {
+    let failures = reports
+        .iter()
+        .filter(|r| r.result == "aborted" || r.result == "max_iterations")
+        .count();
+    let skipped = reports.iter().filter(|r| r.result == "interrupted").count();
+    let time: i64 = reports.iter().map(|r| r.runtime_secs).sum();
+
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str(&format!(
+        "<testsuite name=\"opencode_runner\" tests=\"{}\" failures=\"{}\" errors=\"{}\" skipped=\"{}\" time=\"{}\">\n",
+        reports.len() + errors.len(),
+        failures,
+        errors.len(),
+        skipped,
+        time
+    ));
+    for report in reports {
+        out.push_str(&report.to_junit_testcase());
+    }
+    for error in errors {
+        out.push_str(&format!(
+            "  <testcase classname=\"opencode_runner\" name=\"{}\" time=\"0\">\n",
+            xml_escape(&junit_name(&error.task))
+        ));
+        out.push_str(&format!(
+            "    <error message=\"{}\">{}</error>\n",
+            xml_escape(error.error.lines().next().unwrap_or_default()),
+            xml_escape(&error.error)
+        ));
+        out.push_str("  </testcase>\n");
+    }
+    out.push_str("</testsuite>\n");
+    out
+}
+
+/// Test case name for a task: its first line, cut to 100 characters
+fn junit_name(task: &str) -> String {
+    task.lines()
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .take(100)
+        .collect()
+}
+
+/// Stable identifier for a run result, used in reports
+pub fn result_label(result: &RunResult) -> &'static str {
+    match result {
+        RunResult::Completed => "completed",
+        RunResult::Aborted(_) => "aborted",
+        RunResult::MaxIterations => "max_iterations",
+        RunResult::Interrupted => "interrupted",
+    }
+}
+
+/// Escape text for XML attributes and content
+fn xml_escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            // Control characters other than whitespace are not valid XML
+            c if c.is_control() && !matches!(c, '\n' | '\r' | '\t') => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Make text safe for a single Markdown table cell
+pub(crate) fn markdown_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+/// Write a rendered report, creating parent directories as needed
+pub(crate) fn write_report(path: &Path, contents: &str) -> Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    std::fs::write(path, contents)
+        .with_context(|| format!("Failed to write report to {}", path.display()))
+}
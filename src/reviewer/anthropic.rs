@@ -0,0 +1,166 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use reqwest::Client as HttpClient;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+use super::{
+    api_error, build_prompt_within, parse_decision, ParseStrategy, ParsedDecision, PromptBudget,
+    PromptTemplate, Reviewer, ReviewerContext, ReviewerDecision, DEFAULT_RETRY_BASE_DELAY,
+    SYSTEM_PROMPT,
+};
+
+/// API version sent in the `anthropic-version` header
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+/// Client for the Anthropic Messages API
+pub struct AnthropicReviewer {
+    pub http_client: HttpClient,
+    pub base_url: String,
+    pub model: String,
+    pub api_key: String,
+    pub max_tokens: u32,
+    pub max_retries: u8,
+    /// Delay before the first retry, doubled on each further retry
+    pub retry_base_delay: Duration,
+    /// System prompt sent with every review
+    pub system_prompt: String,
+    /// Template the review prompt is rendered from
+    pub prompt_template: PromptTemplate,
+    /// Limit on the size of the system and user prompts together
+    pub prompt_budget: Option<PromptBudget>,
+}
+
+/// Anthropic Messages API request
+#[derive(Debug, Serialize)]
+struct MessagesRequest {
+    model: String,
+    max_tokens: u32,
+    system: String,
+    messages: Vec<Message>,
+}
+
+#[derive(Debug, Serialize)]
+struct Message {
+    role: String,
+    content: String,
+}
+
+/// Anthropic Messages API response
+#[derive(Debug, Deserialize)]
+struct MessagesResponse {
+    content: Vec<ContentBlock>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ContentBlock {
+    #[serde(rename = "type")]
+    block_type: String,
+    #[serde(default)]
+    text: Option<String>,
+}
+
+impl AnthropicReviewer {
+    /// Create a new Anthropic reviewer, e.g. with base URL `https://api.anthropic.com/v1`
+    pub fn new(base_url: String, model: String, api_key: String) -> Self {
+        let http_client = HttpClient::builder()
+            .timeout(Duration::from_secs(30))
+            .build()
+            .expect("Failed to build HTTP client");
+
+        Self {
+            http_client,
+            base_url,
+            model,
+            api_key,
+            max_tokens: 512,
+            max_retries: 3,
+            retry_base_delay: DEFAULT_RETRY_BASE_DELAY,
+            system_prompt: SYSTEM_PROMPT.to_string(),
+            prompt_template: PromptTemplate::default(),
+            prompt_budget: None,
+        }
+    }
+
+    /// Single review attempt
+    async fn review_once(&self, context: &ReviewerContext) -> Result<ReviewerDecision> {
+        let request = MessagesRequest {
+            model: self.model.clone(),
+            max_tokens: self.max_tokens,
+            system: self.system_prompt.clone(),
+            messages: vec![Message {
+                role: "user".to_string(),
+                content: build_prompt_within(
+                    context,
+                    &self.prompt_template,
+                    &self.system_prompt,
+                    self.prompt_budget,
+                ),
+            }],
+        };
+
+        debug!("Sending review request to {}", self.base_url);
+
+        let url = format!("{}/messages", self.base_url);
+        let response = self
+            .http_client
+            .post(&url)
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", ANTHROPIC_VERSION)
+            .json(&request)
+            .send()
+            .await
+            .context("Failed to send review request")?;
+
+        if !response.status().is_success() {
+            return Err(api_error(response).await);
+        }
+
+        let messages_response: MessagesResponse = response
+            .json()
+            .await
+            .context("Failed to parse reviewer response")?;
+
+        let content = messages_response
+            .content
+            .into_iter()
+            .filter(|block| block.block_type == "text")
+            .filter_map(|block| block.text)
+            .collect::<Vec<_>>()
+            .join("");
+
+        if content.is_empty() {
+            anyhow::bail!("No text content in reviewer response");
+        }
+
+        debug!("Reviewer raw response: {}", content);
+
+        let ParsedDecision { decision, strategy } = parse_decision(&content)?;
+        if strategy != ParseStrategy::Strict {
+            warn!("Recovered reviewer decision using {:?} parsing", strategy);
+        }
+
+        info!(
+            "Reviewer decision: {:?} - {}",
+            decision.action, decision.reason
+        );
+
+        Ok(decision)
+    }
+}
+
+#[async_trait]
+impl Reviewer for AnthropicReviewer {
+    fn name(&self) -> &str {
+        "anthropic"
+    }
+
+    async fn review(&self, context: &ReviewerContext) -> Result<ReviewerDecision> {
+        self.review_once(context).await
+    }
+
+    fn retry_policy(&self) -> (u8, Duration) {
+        (self.max_retries, self.retry_base_delay)
+    }
+}
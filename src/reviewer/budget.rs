@@ -0,0 +1,150 @@
+use super::ReviewerContext;
+
+/// Rough bytes per token for English text and code
+const BYTES_PER_TOKEN: usize = 4;
+
+/// Longest sample line kept in full, in characters
+const MAX_LINE_CHARS: usize = 400;
+
+/// Newest summaries that are never compressed
+const FULL_SUMMARIES: usize = 2;
+
+/// Length older summaries are compressed to, in characters
+const COMPRESSED_SUMMARY_CHARS: usize = 80;
+
+/// Tokens held back when sizing the sample, for the line count in the
+/// prompt and rounding in the estimate
+const SLACK_TOKENS: usize = 8;
+
+/// Longest possible "lines omitted" marker, in bytes
+const MAX_MARKER_BYTES: usize = 48;
+
+/// Estimate the number of tokens in `text`
+pub fn estimate_tokens(text: &str) -> usize {
+    text.len().div_ceil(BYTES_PER_TOKEN)
+}
+
+/// Limit on the size of the reviewer prompt, in estimated tokens
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PromptBudget {
+    pub max_tokens: usize,
+}
+
+impl PromptBudget {
+    pub fn new(max_tokens: usize) -> Self {
+        Self { max_tokens }
+    }
+
+    /// Shrink `context` until the prompt `build` makes from it fits
+    ///
+    /// The least useful text goes first: over-long sample lines are cut,
+    /// then the diff body, then older summaries are compressed and dropped,
+    /// and finally lines are taken out of the middle of the sample.
+    pub fn fit<F>(&self, context: &ReviewerContext, build: F) -> ReviewerContext
+    where
+        F: Fn(&ReviewerContext) -> String,
+    {
+        let fits = |context: &ReviewerContext| estimate_tokens(&build(context)) <= self.max_tokens;
+
+        let mut fitted = context.clone();
+        fitted.current_sample = truncate_long_lines(&fitted.current_sample, MAX_LINE_CHARS);
+        if fits(&fitted) {
+            return fitted;
+        }
+
+        // The stat still shows which files changed
+        if let Some(ref mut diff) = fitted.diff {
+            if !diff.patch.is_empty() {
+                diff.patch.clear();
+                diff.truncated = true;
+                if fits(&fitted) {
+                    return fitted;
+                }
+            }
+        }
+
+        fitted.previous_summaries = compress_summaries(&fitted.previous_summaries);
+        while fitted.previous_summaries.len() > FULL_SUMMARIES && !fits(&fitted) {
+            fitted.previous_summaries.remove(0);
+        }
+        if fits(&fitted) {
+            return fitted;
+        }
+
+        let sample = std::mem::take(&mut fitted.current_sample);
+        let overhead = estimate_tokens(&build(&fitted));
+        let available = self.max_tokens.saturating_sub(overhead + SLACK_TOKENS) * BYTES_PER_TOKEN;
+        fitted.current_sample = trim_middle(&sample, available);
+        fitted
+    }
+}
+
+/// Cut lines longer than `max_chars`, noting how much was dropped
+pub fn truncate_long_lines(text: &str, max_chars: usize) -> String {
+    if text.lines().all(|line| line.len() <= max_chars) {
+        return text.to_string();
+    }
+
+    text.lines()
+        .map(|line| {
+            let chars = line.chars().count();
+            if chars <= max_chars {
+                return line.to_string();
+            }
+            let kept: String = line.chars().take(max_chars).collect();
+            format!("{} ... [{} more chars]", kept, chars - max_chars)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Shorten all but the newest summaries to their opening words
+fn compress_summaries(summaries: &[String]) -> Vec<String> {
+    let full_from = summaries.len().saturating_sub(FULL_SUMMARIES);
+    summaries
+        .iter()
+        .enumerate()
+        .map(|(i, summary)| {
+            if i >= full_from || summary.chars().count() <= COMPRESSED_SUMMARY_CHARS {
+                return summary.clone();
+            }
+            let kept: String = summary.chars().take(COMPRESSED_SUMMARY_CHARS).collect();
+            format!("{}...", kept.trim_end())
+        })
+        .collect()
+}
+
+/// Keep the start and end of `text` within `max_bytes`, replacing the
+/// middle with a marker
+///
+/// A third of the space goes to the start (usually the command or file
+/// being worked on) and the rest to the most recent output.
+pub fn trim_middle(text: &str, max_bytes: usize) -> String {
+    if text.len() <= max_bytes {
+        return text.to_string();
+    }
+
+    let lines: Vec<&str> = text.lines().collect();
+    let room = max_bytes.saturating_sub(MAX_MARKER_BYTES);
+    let mut used = 0;
+    let mut head = 0;
+    while head < lines.len() && used + lines[head].len() < room / 3 {
+        used += lines[head].len() + 1;
+        head += 1;
+    }
+    let mut tail = lines.len();
+    while tail > head && used + lines[tail - 1].len() < room {
+        used += lines[tail - 1].len() + 1;
+        tail -= 1;
+    }
+
+    let omitted = tail - head;
+    if omitted == 0 {
+        return text.to_string();
+    }
+
+    let mut kept: Vec<String> = lines[..head].iter().map(|line| line.to_string()).collect();
+    kept.push(format!("... [{} lines omitted] ...", omitted));
+    kept.extend(lines[tail..].iter().map(|line| line.to_string()));
+    kept.join("\n")
+}
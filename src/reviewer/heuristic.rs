@@ -0,0 +1,189 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use tracing::info;
+
+use super::{Reviewer, ReviewerAction, ReviewerContext, ReviewerDecision};
+
+/// Reviewer that flags looping output without calling a model
+///
+/// Looks only at the sampled worker output, keeping a small history across
+/// iterations so it can spot the worker replaying the same tool calls,
+/// hitting the same error, or producing nothing new.
+pub struct HeuristicReviewer {
+    /// Fraction of repeated lines in a sample that counts as looping
+    pub max_repeated_ratio: f64,
+    /// Samples shorter than this are never judged by repeated-line ratio
+    pub min_lines: usize,
+    /// Abort when the same tool-call sequence shows up this many iterations in a row
+    pub max_identical_tool_runs: usize,
+    /// Abort when the same error line has been seen this many times
+    pub max_error_repeats: usize,
+    /// Abort after this many consecutive iterations without a new line
+    pub max_stalled_iterations: usize,
+    history: Mutex<History>,
+}
+
+/// What the heuristic reviewer remembers between iterations
+#[derive(Default)]
+struct History {
+    /// Tool-call sequence of each reviewed iteration
+    tool_sequences: Vec<Vec<String>>,
+    /// Every line seen in earlier samples
+    seen_lines: HashSet<String>,
+    /// How often each error line has been seen
+    error_counts: HashMap<String, usize>,
+    /// Consecutive iterations with no new lines
+    stalled_iterations: usize,
+}
+
+impl HeuristicReviewer {
+    /// Create a heuristic reviewer with default thresholds
+    pub fn new() -> Self {
+        Self {
+            max_repeated_ratio: 0.6,
+            min_lines: 10,
+            max_identical_tool_runs: 3,
+            max_error_repeats: 3,
+            max_stalled_iterations: 2,
+            history: Mutex::new(History::default()),
+        }
+    }
+
+    /// Fraction of lines in `sample` that duplicate an earlier line
+    pub fn repeated_line_ratio(sample: &str) -> f64 {
+        let lines: Vec<&str> = sample.lines().collect();
+        if lines.is_empty() {
+            return 0.0;
+        }
+
+        let unique: HashSet<&str> = lines.iter().copied().collect();
+        (lines.len() - unique.len()) as f64 / lines.len() as f64
+    }
+
+    /// Tool calls in `sample`, as recorded by the sampler (`[Tool: name detail]`)
+    pub fn tool_sequence(sample: &str) -> Vec<String> {
+        sample
+            .lines()
+            .filter(|line| line.starts_with("[Tool:"))
+            .map(|line| line.to_string())
+            .collect()
+    }
+
+    /// Whether a sampled line reports an error
+    pub fn is_error_line(line: &str) -> bool {
+        line.starts_with("[Error:") || line.to_lowercase().contains("error:")
+    }
+
+    /// Assess a sample and update the cross-iteration history
+    pub fn assess(&self, sample: &str) -> ReviewerDecision {
+        let mut history = self.history.lock().unwrap();
+
+        let lines: Vec<&str> = sample.lines().collect();
+        let ratio = Self::repeated_line_ratio(sample);
+
+        // New output compared to every earlier sample
+        let new_lines = lines
+            .iter()
+            .filter(|line| !history.seen_lines.contains(**line))
+            .count();
+        if new_lines == 0 {
+            history.stalled_iterations += 1;
+        } else {
+            history.stalled_iterations = 0;
+        }
+        history
+            .seen_lines
+            .extend(lines.iter().map(|line| line.to_string()));
+
+        // Tool-call sequence compared to the previous iterations
+        let tools = Self::tool_sequence(sample);
+        history.tool_sequences.push(tools.clone());
+        let identical_tool_runs = if tools.is_empty() {
+            0
+        } else {
+            history
+                .tool_sequences
+                .iter()
+                .rev()
+                .take_while(|sequence| **sequence == tools)
+                .count()
+        };
+
+        // Errors accumulated across iterations
+        let mut worst_error: Option<(String, usize)> = None;
+        for line in lines.iter().filter(|line| Self::is_error_line(line)) {
+            let count = history.error_counts.entry(line.to_string()).or_insert(0);
+            *count += 1;
+            let is_worst = match worst_error {
+                Some((_, worst)) => *count > worst,
+                None => true,
+            };
+            if is_worst {
+                worst_error = Some((line.to_string(), *count));
+            }
+        }
+
+        let abort_reason = if history.stalled_iterations >= self.max_stalled_iterations {
+            Some(format!(
+                "No new output for {} iterations",
+                history.stalled_iterations
+            ))
+        } else if identical_tool_runs >= self.max_identical_tool_runs {
+            Some(format!(
+                "Same {} tool calls repeated for {} iterations",
+                tools.len(),
+                identical_tool_runs
+            ))
+        } else if let Some((error, count)) = worst_error
+            .as_ref()
+            .filter(|(_, count)| *count >= self.max_error_repeats)
+        {
+            Some(format!("Error repeated {} times: {}", count, error))
+        } else if lines.len() >= self.min_lines && ratio >= self.max_repeated_ratio {
+            Some(format!(
+                "{:.0}% of the last {} lines are repeats",
+                ratio * 100.0,
+                lines.len()
+            ))
+        } else {
+            None
+        };
+
+        match abort_reason {
+            Some(reason) => ReviewerDecision {
+                action: ReviewerAction::Abort,
+                reason,
+            },
+            None => ReviewerDecision {
+                action: ReviewerAction::Continue,
+                reason: format!("{} new lines, {:.0}% repeated", new_lines, ratio * 100.0),
+            },
+        }
+    }
+}
+
+impl Default for HeuristicReviewer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Reviewer for HeuristicReviewer {
+    fn name(&self) -> &str {
+        "heuristic"
+    }
+
+    async fn review(&self, context: &ReviewerContext) -> Result<ReviewerDecision> {
+        let decision = self.assess(&context.current_sample);
+
+        info!(
+            "Reviewer decision: {:?} - {}",
+            decision.action, decision.reason
+        );
+
+        Ok(decision)
+    }
+}
@@ -0,0 +1,731 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use reqwest::Client as HttpClient;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::sleep;
+use tracing::{debug, error, info, warn};
+
+use crate::{git::TreeDiff, verify::VerificationResult};
+
+mod anthropic;
+mod budget;
+mod heuristic;
+mod parse;
+mod rate_limit;
+mod scripted;
+mod template;
+
+pub use anthropic::AnthropicReviewer;
+pub use budget::{estimate_tokens, trim_middle, truncate_long_lines, PromptBudget};
+pub use heuristic::HeuristicReviewer;
+pub use parse::{parse_decision, ParseStrategy, ParsedDecision};
+pub use rate_limit::{RateLimitedReviewer, RateLimiter};
+pub use scripted::ScriptedReviewer;
+pub use template::PromptTemplate;
+
+/// System message sent to LLM-backed reviewers
+pub const SYSTEM_PROMPT: &str = "You are a progress monitoring assistant. Analyze the AI assistant's work and determine if it is making progress or stuck in a loop.";
+
+/// Delay before the first retry of a failed review, doubled on each retry
+pub const DEFAULT_RETRY_BASE_DELAY: Duration = Duration::from_secs(1);
+
+/// Review prompt used unless a custom template is configured
+///
+/// See `PromptTemplate` for the placeholders.
+pub const DEFAULT_PROMPT_TEMPLATE: &str = r#"You are monitoring an AI assistant's progress on a task.
+
+Task: {{task}}
+
+Current iteration: {{iteration}}
+
+Assistant status: {{worker_status}}
+
+Previous progress assessments:
+{{summaries}}{{verification}}{{changes}}
+
+Current output (last {{sample_lines}} lines):
+```
+{{sample}}
+```
+
+Assess whether the assistant is:
+1. Making meaningful progress (continue) - the assistant is generating code, making changes, or working toward the goal
+2. Stuck in a loop or not progressing (abort) - the assistant is repeating itself, going in circles, or clearly failing to make progress
+3. Drifting but recoverable (redirect) - the assistant needs a short correction to get back on track; put the instruction for the assistant in "guidance"
+4. Finished (complete) - the assistant has accomplished the task and stopped working
+
+Respond with JSON in this exact format:
+{
+  "action": "continue|abort|redirect|complete",
+  "reason": "Brief explanation of your assessment",
+  "guidance": "Only for redirect: one or two sentences addressed to the assistant"
+}"#;
+
+/// A backend that reviews worker progress and decides what the loop does next
+#[async_trait]
+pub trait Reviewer: Send + Sync {
+    /// Short name of the backend, used in logs
+    fn name(&self) -> &str;
+
+    /// Single review attempt
+    async fn review(&self, context: &ReviewerContext) -> Result<ReviewerDecision>;
+
+    /// Attempts made before falling back to Continue, and the delay before
+    /// the first retry
+    fn retry_policy(&self) -> (u8, Duration) {
+        (1, Duration::ZERO)
+    }
+
+    /// Review progress, retrying failed attempts with exponential backoff
+    /// Returns Continue if all attempts fail
+    async fn review_with_retry(&self, context: &ReviewerContext) -> Result<ReviewOutcome> {
+        let (max_retries, base_delay) = self.retry_policy();
+        Ok(retry_with_backoff(max_retries, base_delay, || self.review(context)).await)
+    }
+}
+
+/// Reviewer backends selectable from the command line
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ReviewerBackend {
+    /// OpenAI-compatible `/chat/completions` endpoint
+    #[value(name = "openai")]
+    #[serde(rename = "openai")]
+    OpenAi,
+    /// Anthropic Messages API (reads `ANTHROPIC_API_KEY`)
+    Anthropic,
+    /// Offline heuristics over the sampled output, no model needed
+    Heuristic,
+}
+
+impl ReviewerBackend {
+    /// Name used on the command line and by the built reviewer
+    pub fn name(self) -> &'static str {
+        match self {
+            ReviewerBackend::OpenAi => "openai",
+            ReviewerBackend::Anthropic => "anthropic",
+            ReviewerBackend::Heuristic => "heuristic",
+        }
+    }
+
+    /// Build the reviewer for this backend
+    pub fn build(self, base_url: String, model: String) -> Result<Arc<dyn Reviewer>> {
+        self.build_with_options(base_url, model, ReviewerOptions::default())
+    }
+
+    /// Build the reviewer, applying `options` to the LLM backends
+    pub fn build_with_options(
+        self,
+        base_url: String,
+        model: String,
+        options: ReviewerOptions,
+    ) -> Result<Arc<dyn Reviewer>> {
+        let prompt_budget = options.token_budget.map(PromptBudget::new);
+        let prompt_template = options.prompt_template.unwrap_or_default();
+        let reviewer: Arc<dyn Reviewer> = match self {
+            ReviewerBackend::OpenAi => {
+                let mut client = ReviewerClient::new(base_url, model);
+                if let Some(system_prompt) = options.system_prompt {
+                    client.system_prompt = system_prompt;
+                }
+                client.prompt_template = prompt_template;
+                client.prompt_budget = prompt_budget;
+                Arc::new(client)
+            }
+            ReviewerBackend::Anthropic => {
+                let api_key = std::env::var("ANTHROPIC_API_KEY")
+                    .context("ANTHROPIC_API_KEY is required for the anthropic reviewer")?;
+                let mut client = AnthropicReviewer::new(base_url, model, api_key);
+                if let Some(system_prompt) = options.system_prompt {
+                    client.system_prompt = system_prompt;
+                }
+                client.prompt_template = prompt_template;
+                client.prompt_budget = prompt_budget;
+                Arc::new(client)
+            }
+            ReviewerBackend::Heuristic => Arc::new(HeuristicReviewer::new()),
+        };
+        Ok(reviewer)
+    }
+}
+
+/// Prompt settings for the LLM-backed reviewers
+#[derive(Debug, Clone, Default)]
+pub struct ReviewerOptions {
+    /// System message replacing `SYSTEM_PROMPT`
+    pub system_prompt: Option<String>,
+    /// Review prompt replacing `DEFAULT_PROMPT_TEMPLATE`
+    pub prompt_template: Option<PromptTemplate>,
+    /// Limit on the prompt size in estimated tokens
+    pub token_budget: Option<usize>,
+}
+
+impl ReviewerOptions {
+    /// Options with the system prompt and review template read from files
+    ///
+    /// `system_prompt_file` takes precedence over `system_prompt`.
+    pub fn load(
+        system_prompt: Option<&str>,
+        system_prompt_file: Option<&Path>,
+        prompt_template: Option<&Path>,
+        token_budget: Option<usize>,
+    ) -> Result<Self> {
+        let system_prompt = match system_prompt_file {
+            Some(path) => {
+                let prompt = std::fs::read_to_string(path)
+                    .with_context(|| format!("Failed to read system prompt {}", path.display()))?;
+                if prompt.trim().is_empty() {
+                    anyhow::bail!("System prompt {} is empty", path.display());
+                }
+                Some(prompt.trim_end().to_string())
+            }
+            None => system_prompt.map(|prompt| prompt.to_string()),
+        };
+
+        Ok(Self {
+            system_prompt,
+            prompt_template: prompt_template.map(PromptTemplate::load).transpose()?,
+            token_budget,
+        })
+    }
+}
+
+/// Decision from the reviewer
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ReviewerDecision {
+    #[serde(flatten)]
+    pub action: ReviewerAction,
+    pub reason: String,
+}
+
+/// Decision from a review and how many retries it took
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReviewOutcome {
+    pub decision: ReviewerDecision,
+    /// Failed attempts before the decision, or before giving up
+    pub retries: u8,
+}
+
+impl ReviewOutcome {
+    /// Decision reached on the first attempt
+    pub fn first_try(decision: ReviewerDecision) -> Self {
+        Self {
+            decision,
+            retries: 0,
+        }
+    }
+}
+
+/// Possible reviewer actions
+///
+/// Serialized with the action name in an `action` field, so a redirect
+/// looks like `{"action": "redirect", "guidance": "...", "reason": "..."}`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "action", rename_all = "lowercase")]
+pub enum ReviewerAction {
+    /// Worker is making progress, continue
+    Continue,
+    /// Worker is stuck or looping, abort
+    Abort,
+    /// Worker is drifting, send corrective guidance into the session
+    Redirect { guidance: String },
+    /// Worker has finished the task
+    Complete,
+}
+
+/// Context provided to the reviewer
+#[derive(Debug, Clone, Default)]
+pub struct ReviewerContext {
+    /// The original task description
+    pub task_description: String,
+    /// Current iteration number
+    pub iteration: usize,
+    /// Previous reviewer summaries
+    pub previous_summaries: Vec<String>,
+    /// Current sample of worker output (last 100 lines)
+    pub current_sample: String,
+    /// Whether the session went idle after the worker's last message
+    pub worker_idle: bool,
+    /// Verification commands run when the worker last claimed to be done
+    pub verification: Vec<VerificationResult>,
+    /// Working tree changes since the previous review, `None` when the
+    /// working directory is not tracked by git
+    pub diff: Option<TreeDiff>,
+}
+
+/// Client for the reviewer API (OpenAI-compatible)
+pub struct ReviewerClient {
+    pub http_client: HttpClient,
+    pub base_url: String,
+    pub model: String,
+    pub max_retries: u8,
+    /// Delay before the first retry, doubled on each further retry
+    pub retry_base_delay: Duration,
+    /// System message sent with every review
+    pub system_prompt: String,
+    /// Template the review prompt is rendered from
+    pub prompt_template: PromptTemplate,
+    /// Limit on the size of the system and user prompts together
+    pub prompt_budget: Option<PromptBudget>,
+}
+
+/// OpenAI-compatible chat message
+#[derive(Debug, Serialize)]
+struct ChatMessage {
+    role: String,
+    content: String,
+}
+
+/// OpenAI-compatible request
+#[derive(Debug, Serialize)]
+struct ChatRequest {
+    model: String,
+    messages: Vec<ChatMessage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<ResponseFormat>,
+}
+
+#[derive(Debug, Serialize)]
+struct ResponseFormat {
+    #[serde(rename = "type")]
+    format_type: String,
+}
+
+/// OpenAI-compatible response
+#[derive(Debug, Deserialize)]
+struct ChatResponse {
+    choices: Vec<Choice>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Choice {
+    message: ResponseMessage,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResponseMessage {
+    content: String,
+}
+
+impl ReviewerClient {
+    /// Create a new reviewer client
+    pub fn new(base_url: String, model: String) -> Self {
+        let http_client = HttpClient::builder()
+            .timeout(Duration::from_secs(30))
+            .build()
+            .expect("Failed to build HTTP client");
+
+        Self {
+            http_client,
+            base_url,
+            model,
+            max_retries: 3,
+            retry_base_delay: DEFAULT_RETRY_BASE_DELAY,
+            system_prompt: SYSTEM_PROMPT.to_string(),
+            prompt_template: PromptTemplate::default(),
+            prompt_budget: None,
+        }
+    }
+
+    /// Single review attempt
+    async fn review_once(&self, context: &ReviewerContext) -> Result<ReviewerDecision> {
+        let prompt = self.build_prompt(context);
+
+        let request = ChatRequest {
+            model: self.model.clone(),
+            messages: vec![
+                ChatMessage {
+                    role: "system".to_string(),
+                    content: self.system_prompt.clone(),
+                },
+                ChatMessage {
+                    role: "user".to_string(),
+                    content: prompt,
+                },
+            ],
+            response_format: Some(ResponseFormat {
+                format_type: "json_object".to_string(),
+            }),
+        };
+
+        debug!("Sending review request to {}", self.base_url);
+
+        let url = format!("{}/chat/completions", self.base_url);
+        let response = self
+            .http_client
+            .post(&url)
+            .json(&request)
+            .send()
+            .await
+            .context("Failed to send review request")?;
+
+        if !response.status().is_success() {
+            return Err(api_error(response).await);
+        }
+
+        let chat_response: ChatResponse = response
+            .json()
+            .await
+            .context("Failed to parse reviewer response")?;
+
+        let content = chat_response
+            .choices
+            .first()
+            .map(|c| c.message.content.clone())
+            .context("No choices in reviewer response")?;
+
+        debug!("Reviewer raw response: {}", content);
+
+        let ParsedDecision { decision, strategy } = parse_decision(&content)?;
+        if strategy != ParseStrategy::Strict {
+            warn!("Recovered reviewer decision using {:?} parsing", strategy);
+        }
+
+        info!(
+            "Reviewer decision: {:?} - {}",
+            decision.action, decision.reason
+        );
+
+        Ok(decision)
+    }
+
+    /// Build the prompt for the reviewer
+    pub fn build_prompt(&self, context: &ReviewerContext) -> String {
+        build_prompt_within(
+            context,
+            &self.prompt_template,
+            &self.system_prompt,
+            self.prompt_budget,
+        )
+    }
+
+    /// Get a summary string for the activity log
+    pub fn format_decision_summary(decision: &ReviewerDecision, iteration: usize) -> String {
+        format!(
+            "Iter {}: {:?} - {}",
+            iteration,
+            decision.action,
+            decision.reason
+        )
+    }
+}
+
+#[async_trait]
+impl Reviewer for ReviewerClient {
+    fn name(&self) -> &str {
+        "openai"
+    }
+
+    async fn review(&self, context: &ReviewerContext) -> Result<ReviewerDecision> {
+        self.review_once(context).await
+    }
+
+    fn retry_policy(&self) -> (u8, Duration) {
+        (self.max_retries, self.retry_base_delay)
+    }
+}
+
+/// Delay an API asked for in its `Retry-After` header, attached to the
+/// error for the rejected request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryAfter(pub Duration);
+
+impl std::fmt::Display for RetryAfter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "retry after {:?}", self.0)
+    }
+}
+
+impl std::error::Error for RetryAfter {}
+
+/// Delay requested by the API that caused `error`, if any
+pub fn retry_after(error: &anyhow::Error) -> Option<Duration> {
+    error.downcast_ref::<RetryAfter>().map(|retry| retry.0)
+}
+
+/// Error for an unsuccessful API response, keeping its `Retry-After`
+pub(crate) async fn api_error(response: reqwest::Response) -> anyhow::Error {
+    let status = response.status();
+    let delay = response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse().ok())
+        .map(Duration::from_secs);
+    let text = response
+        .text()
+        .await
+        .unwrap_or_else(|_| "Unknown error".to_string());
+
+    let message = format!("Reviewer API returned error {}: {}", status, text);
+    match delay {
+        Some(delay) => anyhow::Error::new(RetryAfter(delay)).context(message),
+        None => anyhow::anyhow!(message),
+    }
+}
+
+/// Run review attempts with exponential backoff starting at `base_delay`,
+/// waiting longer if the API asks to
+/// Returns Continue if all retries fail
+pub(crate) async fn retry_with_backoff<F, Fut>(
+    max_retries: u8,
+    base_delay: Duration,
+    mut attempt_fn: F,
+) -> ReviewOutcome
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<ReviewerDecision>>,
+{
+    for attempt in 0..max_retries {
+        match attempt_fn().await {
+            Ok(decision) => {
+                if attempt > 0 {
+                    info!("Reviewer succeeded after {} retries", attempt);
+                }
+                return ReviewOutcome {
+                    decision,
+                    retries: attempt,
+                };
+            }
+            Err(e) if attempt + 1 < max_retries => {
+                let backoff = base_delay * 2u32.pow(attempt as u32);
+                let delay = retry_after(&e).map_or(backoff, |after| after.max(backoff));
+                warn!(
+                    "Reviewer failed (attempt {}): {}, retrying in {:?}",
+                    attempt + 1,
+                    e,
+                    delay
+                );
+                sleep(delay).await;
+            }
+            Err(e) => {
+                warn!("Reviewer failed (attempt {}): {}", attempt + 1, e);
+            }
+        }
+    }
+
+    // Default to Continue after max retries
+    error!(
+        "Reviewer failed after {} retries, defaulting to Continue",
+        max_retries
+    );
+    ReviewOutcome {
+        decision: ReviewerDecision {
+            action: ReviewerAction::Continue,
+            reason: format!(
+                "Reviewer API unavailable after {} retries, continuing based on last known state",
+                max_retries
+            ),
+        },
+        retries: max_retries.saturating_sub(1),
+    }
+}
+
+/// Render `template`, shrinking the context so the prompt and
+/// `system_prompt` together fit `budget`
+pub fn build_prompt_within(
+    context: &ReviewerContext,
+    template: &PromptTemplate,
+    system_prompt: &str,
+    budget: Option<PromptBudget>,
+) -> String {
+    let Some(budget) = budget else {
+        return template.render(context);
+    };
+
+    let budget = PromptBudget::new(
+        budget
+            .max_tokens
+            .saturating_sub(estimate_tokens(system_prompt)),
+    );
+    let prompt = template.render(&budget.fit(context, |context| template.render(context)));
+    debug!(
+        "Reviewer prompt is ~{} tokens (budget {})",
+        estimate_tokens(&prompt),
+        budget.max_tokens
+    );
+    prompt
+}
+
+/// Build the prompt for LLM-backed reviewers from the default template
+pub fn build_prompt(context: &ReviewerContext) -> String {
+    PromptTemplate::default().render(context)
+}
+
+/// Numbered list of previous summaries
+fn format_summaries(summaries: &[String]) -> String {
+    if summaries.is_empty() {
+        return "No previous assessments.".to_string();
+    }
+    summaries
+        .iter()
+        .enumerate()
+        .map(|(i, s)| format!("{}. {}", i + 1, s))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn worker_status(context: &ReviewerContext) -> &'static str {
+    if context.worker_idle {
+        "finished its turn and is waiting for input"
+    } else {
+        "still working"
+    }
+}
+
+/// Prompt section listing the last verification run, empty if none
+fn format_verification(results: &[VerificationResult]) -> String {
+    let mut verification = String::new();
+    if !results.is_empty() {
+        verification.push_str(
+            "\n\nVerification of the assistant's last attempt to finish (failures were sent back to it):",
+        );
+        for result in results {
+            verification.push_str(&format!("\n- {}", result.summary()));
+            if !result.success {
+                verification.push_str(&format!("\n```\n{}\n```", result.output));
+            }
+        }
+    }
+    verification
+}
+
+/// Prompt section describing working tree changes since the previous review
+fn format_changes(diff: &TreeDiff) -> String {
+    if diff.is_empty() {
+        return match diff.unchanged_reviews {
+            0 | 1 => "\n\nFiles changed since the previous review: none".to_string(),
+            n => format!(
+                "\n\nFiles changed since the previous review: none (no files changed for {} reviews)",
+                n
+            ),
+        };
+    }
+
+    let truncated = if diff.truncated {
+        "\n... (diff truncated)"
+    } else {
+        ""
+    };
+    format!(
+        "\n\nFiles changed since the previous review:\n{}\n```diff\n{}{}\n```",
+        diff.stat, diff.patch, truncated
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_decision() {
+        let json = r#"{"action": "continue", "reason": "Making progress"}"#;
+        let decision: ReviewerDecision = serde_json::from_str(json).unwrap();
+        
+        match decision.action {
+            ReviewerAction::Continue => {}
+            _ => panic!("Expected Continue"),
+        }
+        assert_eq!(decision.reason, "Making progress");
+    }
+
+    #[test]
+    fn test_parse_abort() {
+        let json = r#"{"action": "abort", "reason": "Stuck in loop"}"#;
+        let decision: ReviewerDecision = serde_json::from_str(json).unwrap();
+        
+        match decision.action {
+            ReviewerAction::Abort => {}
+            _ => panic!("Expected Abort"),
+        }
+    }
+
+    #[test]
+    fn test_parse_redirect() {
+        let json = r#"{"action": "redirect", "reason": "Editing the wrong file", "guidance": "The bug is in src/parser.rs, not src/lexer.rs"}"#;
+        let decision: ReviewerDecision = serde_json::from_str(json).unwrap();
+
+        match decision.action {
+            ReviewerAction::Redirect { guidance } => {
+                assert_eq!(guidance, "The bug is in src/parser.rs, not src/lexer.rs");
+            }
+            _ => panic!("Expected Redirect"),
+        }
+        assert_eq!(decision.reason, "Editing the wrong file");
+    }
+
+    #[test]
+    fn test_parse_complete() {
+        let json = r#"{"action": "complete", "reason": "All tests pass"}"#;
+        let decision: ReviewerDecision = serde_json::from_str(json).unwrap();
+
+        assert_eq!(decision.action, ReviewerAction::Complete);
+    }
+
+    #[test]
+    fn test_prompt_reports_idle_worker() {
+        let client =
+            ReviewerClient::new("http://localhost:11434".to_string(), "llama3".to_string());
+        let context = ReviewerContext {
+            task_description: "Task".to_string(),
+            iteration: 2,
+            current_sample: "Done.".to_string(),
+            worker_idle: true,
+            ..Default::default()
+        };
+
+        let prompt = client.build_prompt(&context);
+        assert!(prompt.contains("Assistant status: finished its turn"));
+        assert!(!prompt.contains("Verification"));
+    }
+
+    #[test]
+    fn test_prompt_includes_failed_verification() {
+        let context = ReviewerContext {
+            task_description: "Task".to_string(),
+            iteration: 3,
+            verification: vec![VerificationResult {
+                command: "cargo test".to_string(),
+                success: false,
+                exit_code: Some(101),
+                output: "test parser::tests::empty ... FAILED".to_string(),
+                duration_ms: 1200,
+            }],
+            ..Default::default()
+        };
+
+        let prompt = build_prompt(&context);
+        assert!(!prompt.contains("Files changed"));
+        assert!(prompt.contains("- `cargo test` failed (exit 101)"));
+        assert!(prompt.contains("test parser::tests::empty ... FAILED"));
+    }
+
+    #[test]
+    fn test_prompt_includes_diff() {
+        let mut context = ReviewerContext {
+            task_description: "Task".to_string(),
+            diff: Some(TreeDiff {
+                stat: "src/lib.rs | 2 +-\n 1 file changed".to_string(),
+                patch: "-old\n+new".to_string(),
+                truncated: true,
+                unchanged_reviews: 0,
+            }),
+            ..Default::default()
+        };
+
+        let prompt = build_prompt(&context);
+        assert!(prompt.contains("Files changed since the previous review:\nsrc/lib.rs | 2 +-"));
+        assert!(prompt.contains("```diff\n-old\n+new\n... (diff truncated)\n```"));
+
+        context.diff = Some(TreeDiff {
+            unchanged_reviews: 3,
+            ..Default::default()
+        });
+        let prompt = build_prompt(&context);
+        assert!(prompt.contains("none (no files changed for 3 reviews)"));
+    }
+}
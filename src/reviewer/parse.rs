@@ -0,0 +1,223 @@
+use anyhow::Result;
+use serde_json::Value;
+
+use super::{ReviewerAction, ReviewerDecision};
+
+/// Longest stretch of the reply quoted in a keyword-classified reason
+const MAX_QUOTED_CHARS: usize = 200;
+
+/// How a decision was recovered from the reviewer's reply
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseStrategy {
+    /// The reply was exactly the decision JSON
+    Strict,
+    /// The first JSON object in the reply, with the action normalized
+    Extracted,
+    /// As `Extracted`, after removing trailing commas
+    Repaired,
+    /// No usable JSON, the action was the only one named in the text
+    Keywords,
+}
+
+/// Decision parsed from a reviewer reply, with how it was found
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedDecision {
+    pub decision: ReviewerDecision,
+    pub strategy: ParseStrategy,
+}
+
+/// The four action names the prompt asks for, and the synonyms models
+/// use instead
+const SYNONYMS: &[(&str, Action)] = &[
+    ("continue", Action::Continue),
+    ("proceed", Action::Continue),
+    ("progressing", Action::Continue),
+    ("abort", Action::Abort),
+    ("stop", Action::Abort),
+    ("halt", Action::Abort),
+    ("stuck", Action::Abort),
+    ("redirect", Action::Redirect),
+    ("steer", Action::Redirect),
+    ("complete", Action::Complete),
+    ("completed", Action::Complete),
+    ("done", Action::Complete),
+    ("finished", Action::Complete),
+];
+
+/// Action without its guidance, as named in a reply
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Action {
+    Continue,
+    Abort,
+    Redirect,
+    Complete,
+}
+
+impl Action {
+    fn from_name(name: &str) -> Option<Self> {
+        let name = name.trim().trim_matches(|c: char| !c.is_alphanumeric());
+        SYNONYMS
+            .iter()
+            .find(|(synonym, _)| synonym.eq_ignore_ascii_case(name))
+            .map(|(_, action)| *action)
+    }
+}
+
+/// Parse a reviewer reply, trying progressively looser strategies
+///
+/// Replies wrapped in code fences or prose, with capitalized or synonymous
+/// action names, or with trailing commas still parse. Failing that, a reply
+/// naming exactly one action, by name or synonym, is taken as that action,
+/// unless it is a redirect,
+/// which needs guidance, or completion, which is too costly to guess.
+/// Anything else is an error.
+pub fn parse_decision(content: &str) -> Result<ParsedDecision> {
+    if let Ok(decision) = serde_json::from_str::<ReviewerDecision>(content.trim()) {
+        return Ok(ParsedDecision {
+            decision,
+            strategy: ParseStrategy::Strict,
+        });
+    }
+
+    if let Some(object) = first_json_object(content) {
+        if let Some(decision) = serde_json::from_str(object)
+            .ok()
+            .and_then(|value| decision_from_value(&value))
+        {
+            return Ok(ParsedDecision {
+                decision,
+                strategy: ParseStrategy::Extracted,
+            });
+        }
+
+        let repaired = remove_trailing_commas(object);
+        if let Some(decision) = serde_json::from_str(&repaired)
+            .ok()
+            .and_then(|value| decision_from_value(&value))
+        {
+            return Ok(ParsedDecision {
+                decision,
+                strategy: ParseStrategy::Repaired,
+            });
+        }
+    }
+
+    if let Some(decision) = classify_keywords(content) {
+        return Ok(ParsedDecision {
+            decision,
+            strategy: ParseStrategy::Keywords,
+        });
+    }
+
+    anyhow::bail!("Failed to parse reviewer decision from: {}", content)
+}
+
+/// Build a decision from a JSON object with a recognizable action
+fn decision_from_value(value: &Value) -> Option<ReviewerDecision> {
+    let object = value.as_object()?;
+    let text = |key: &str| {
+        object
+            .get(key)
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+    };
+
+    let action = match Action::from_name(text("action")?)? {
+        Action::Continue => ReviewerAction::Continue,
+        Action::Abort => ReviewerAction::Abort,
+        Action::Redirect => ReviewerAction::Redirect {
+            guidance: text("guidance")?.to_string(),
+        },
+        Action::Complete => ReviewerAction::Complete,
+    };
+
+    Some(ReviewerDecision {
+        action,
+        reason: text("reason").unwrap_or("No reason given").to_string(),
+    })
+}
+
+/// The first balanced `{...}` in `text`, ignoring braces inside strings
+fn first_json_object(text: &str) -> Option<&str> {
+    let start = text.find('{')?;
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (offset, c) in text[start..].char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(&text[start..start + offset + 1]);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Drop commas directly before a closing `}` or `]`, outside strings
+fn remove_trailing_commas(json: &str) -> String {
+    let mut out = String::with_capacity(json.len());
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut chars = json.chars();
+
+    while let Some(c) = chars.next() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+        } else if c == '"' {
+            in_string = true;
+        } else if c == ',' {
+            let rest = chars.clone().find(|next| !next.is_whitespace());
+            if matches!(rest, Some('}') | Some(']')) {
+                continue;
+            }
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Decide from free text naming exactly one action, continue or abort,
+/// possibly several times or by different synonyms
+fn classify_keywords(content: &str) -> Option<ReviewerDecision> {
+    let mut named = content
+        .split(|c: char| !c.is_alphanumeric())
+        .filter_map(Action::from_name);
+    let first = named.next()?;
+    if named.any(|action| action != first) {
+        return None;
+    }
+
+    let action = match first {
+        Action::Continue => ReviewerAction::Continue,
+        Action::Abort => ReviewerAction::Abort,
+        Action::Redirect | Action::Complete => return None,
+    };
+
+    let quoted: String = content.trim().chars().take(MAX_QUOTED_CHARS).collect();
+    Some(ReviewerDecision {
+        action,
+        reason: format!("Classified from reply text: {}", quoted),
+    })
+}
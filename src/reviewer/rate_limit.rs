@@ -0,0 +1,100 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Mutex, Semaphore};
+use tokio::time::Instant;
+use tracing::debug;
+
+use super::{retry_after, Reviewer, ReviewerContext, ReviewerDecision};
+
+/// Limits reviewer requests shared by concurrent runs
+///
+/// At most `max_concurrent` reviews are in flight at once, and reviews
+/// start at least `min_interval` apart.
+pub struct RateLimiter {
+    permits: Semaphore,
+    min_interval: Duration,
+    next_start: Mutex<Option<Instant>>,
+}
+
+impl RateLimiter {
+    /// Create a limiter; `max_concurrent` is raised to at least 1
+    pub fn new(max_concurrent: usize, min_interval: Duration) -> Self {
+        Self {
+            permits: Semaphore::new(max_concurrent.max(1)),
+            min_interval,
+            next_start: Mutex::new(None),
+        }
+    }
+
+    /// Run `review` once a slot is free and the interval has passed
+    pub async fn run<F, T>(&self, review: F) -> T
+    where
+        F: std::future::Future<Output = T>,
+    {
+        let _permit = self
+            .permits
+            .acquire()
+            .await
+            .expect("rate limiter semaphore is never closed");
+
+        {
+            let mut next_start = self.next_start.lock().await;
+            if let Some(at) = *next_start {
+                if at > Instant::now() {
+                    debug!("Rate limiting reviewer for {:?}", at - Instant::now());
+                    tokio::time::sleep_until(at).await;
+                }
+            }
+            *next_start = Some(Instant::now() + self.min_interval);
+        }
+
+        review.await
+    }
+
+    /// Hold back the next review until at least `delay` from now
+    pub async fn defer(&self, delay: Duration) {
+        let until = Instant::now() + delay;
+        let mut next_start = self.next_start.lock().await;
+        if !next_start.is_some_and(|at| at >= until) {
+            *next_start = Some(until);
+        }
+    }
+}
+
+/// Reviewer whose requests go through a shared [`RateLimiter`]
+///
+/// Each attempt takes its own slot, so retries are spaced like any other
+/// request, and a `Retry-After` from the API holds back every run sharing
+/// the limiter.
+pub struct RateLimitedReviewer {
+    inner: Arc<dyn Reviewer>,
+    limiter: Arc<RateLimiter>,
+}
+
+impl RateLimitedReviewer {
+    /// Wrap `inner` so its reviews are limited by `limiter`
+    pub fn new(inner: Arc<dyn Reviewer>, limiter: Arc<RateLimiter>) -> Self {
+        Self { inner, limiter }
+    }
+}
+
+#[async_trait]
+impl Reviewer for RateLimitedReviewer {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    async fn review(&self, context: &ReviewerContext) -> Result<ReviewerDecision> {
+        let result = self.limiter.run(self.inner.review(context)).await;
+        if let Some(delay) = result.as_ref().err().and_then(retry_after) {
+            self.limiter.defer(delay).await;
+        }
+        result
+    }
+
+    fn retry_policy(&self) -> (u8, Duration) {
+        self.inner.retry_policy()
+    }
+}
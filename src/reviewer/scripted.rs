@@ -0,0 +1,57 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use super::{Reviewer, ReviewerAction, ReviewerContext, ReviewerDecision};
+
+/// Reviewer that replays a fixed list of decisions, for tests
+///
+/// Once the script runs out it keeps answering Continue.
+pub struct ScriptedReviewer {
+    decisions: Mutex<VecDeque<ReviewerDecision>>,
+    contexts: Mutex<Vec<ReviewerContext>>,
+}
+
+impl ScriptedReviewer {
+    /// Create a reviewer that returns `decisions` in order
+    pub fn new(decisions: Vec<ReviewerDecision>) -> Self {
+        Self {
+            decisions: Mutex::new(decisions.into()),
+            contexts: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Every context this reviewer has been asked to review, in order
+    pub fn contexts(&self) -> Vec<ReviewerContext> {
+        self.contexts.lock().unwrap().clone()
+    }
+
+    /// Number of scripted decisions not yet returned
+    pub fn remaining(&self) -> usize {
+        self.decisions.lock().unwrap().len()
+    }
+}
+
+#[async_trait]
+impl Reviewer for ScriptedReviewer {
+    fn name(&self) -> &str {
+        "scripted"
+    }
+
+    async fn review(&self, context: &ReviewerContext) -> Result<ReviewerDecision> {
+        self.contexts.lock().unwrap().push(context.clone());
+
+        let decision = self
+            .decisions
+            .lock()
+            .unwrap()
+            .pop_front()
+            .unwrap_or_else(|| ReviewerDecision {
+                action: ReviewerAction::Continue,
+                reason: "Script exhausted".to_string(),
+            });
+
+        Ok(decision)
+    }
+}
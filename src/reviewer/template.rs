@@ -0,0 +1,180 @@
+use anyhow::{Context, Result};
+use std::path::Path;
+
+use super::{
+    format_changes, format_summaries, format_verification, worker_status, ReviewerContext,
+    DEFAULT_PROMPT_TEMPLATE,
+};
+
+/// A value from the review context that a template can place
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Task,
+    Iteration,
+    WorkerStatus,
+    Summaries,
+    Verification,
+    Changes,
+    SampleLines,
+    Sample,
+}
+
+impl Field {
+    const ALL: [Field; 8] = [
+        Field::Task,
+        Field::Iteration,
+        Field::WorkerStatus,
+        Field::Summaries,
+        Field::Verification,
+        Field::Changes,
+        Field::SampleLines,
+        Field::Sample,
+    ];
+
+    /// Fields every template must use
+    const REQUIRED: [Field; 2] = [Field::Task, Field::Sample];
+
+    fn name(self) -> &'static str {
+        match self {
+            Field::Task => "task",
+            Field::Iteration => "iteration",
+            Field::WorkerStatus => "worker_status",
+            Field::Summaries => "summaries",
+            Field::Verification => "verification",
+            Field::Changes => "changes",
+            Field::SampleLines => "sample_lines",
+            Field::Sample => "sample",
+        }
+    }
+
+    /// Other name the field can be placed under
+    fn alias(self) -> Option<&'static str> {
+        match self {
+            Field::Changes => Some("diff"),
+            _ => None,
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|field| field.name() == name || field.alias() == Some(name))
+    }
+
+    fn render(self, context: &ReviewerContext) -> String {
+        match self {
+            Field::Task => context.task_description.clone(),
+            Field::Iteration => context.iteration.to_string(),
+            Field::WorkerStatus => worker_status(context).to_string(),
+            Field::Summaries => format_summaries(&context.previous_summaries),
+            Field::Verification => format_verification(&context.verification),
+            Field::Changes => context
+                .diff
+                .as_ref()
+                .map(format_changes)
+                .unwrap_or_default(),
+            Field::SampleLines => context.current_sample.lines().count().to_string(),
+            Field::Sample => context.current_sample.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Text(String),
+    Field(Field),
+}
+
+/// Review prompt with `{{placeholder}}` fields filled in from the context
+///
+/// Placeholders:
+/// - `{{task}}`, `{{sample}}` (both required)
+/// - `{{iteration}}`, `{{worker_status}}`, `{{summaries}}`, `{{sample_lines}}`
+/// - `{{verification}}`, `{{changes}}` (or `{{diff}}`): sections starting
+///   with a blank line, empty when there were no verification results or
+///   no diff
+#[derive(Debug, Clone, PartialEq)]
+pub struct PromptTemplate {
+    segments: Vec<Segment>,
+}
+
+impl Default for PromptTemplate {
+    fn default() -> Self {
+        Self::parse(DEFAULT_PROMPT_TEMPLATE).expect("default prompt template is valid")
+    }
+}
+
+impl PromptTemplate {
+    /// Parse a template, checking every placeholder is known and the
+    /// required ones are present
+    pub fn parse(source: &str) -> Result<Self> {
+        let mut segments = Vec::new();
+        let mut offset = 0;
+
+        while let Some(start) = source[offset..].find("{{") {
+            let start = offset + start;
+            if start > offset {
+                segments.push(Segment::Text(source[offset..start].to_string()));
+            }
+
+            let line = source[..start].matches('\n').count() + 1;
+            let end = source[start + 2..]
+                .find("}}")
+                .with_context(|| format!("Unclosed placeholder on line {}", line))?;
+            let name = source[start + 2..start + 2 + end].trim();
+            let field = Field::from_name(name).with_context(|| {
+                format!(
+                    "Unknown placeholder {{{{{}}}}} on line {}, expected one of: {}",
+                    name,
+                    line,
+                    placeholder_list(&Field::ALL)
+                )
+            })?;
+            segments.push(Segment::Field(field));
+            offset = start + 2 + end + 2;
+        }
+        if offset < source.len() {
+            segments.push(Segment::Text(source[offset..].to_string()));
+        }
+
+        let missing: Vec<Field> = Field::REQUIRED
+            .into_iter()
+            .filter(|field| !segments.contains(&Segment::Field(*field)))
+            .collect();
+        if !missing.is_empty() {
+            anyhow::bail!(
+                "Prompt template is missing required placeholders: {}",
+                placeholder_list(&missing)
+            );
+        }
+
+        Ok(Self { segments })
+    }
+
+    /// Read and parse a template file
+    pub fn load(path: &Path) -> Result<Self> {
+        let source = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read prompt template {}", path.display()))?;
+        Self::parse(&source).with_context(|| format!("Invalid prompt template {}", path.display()))
+    }
+
+    /// Fill in the placeholders from `context`
+    pub fn render(&self, context: &ReviewerContext) -> String {
+        let mut out = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Text(text) => out.push_str(text),
+                Segment::Field(field) => out.push_str(&field.render(context)),
+            }
+        }
+        out
+    }
+}
+
+fn placeholder_list(fields: &[Field]) -> String {
+    fields
+        .iter()
+        .map(|field| format!("{{{{{}}}}}", field.name()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -0,0 +1,168 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+use crate::{
+    config::ControlConfig,
+    git::{ignore_dir, Worktree},
+    reviewer::ReviewerBackend,
+    state::State,
+};
+
+/// File inside the run directory holding the run record
+const RUN_FILE: &str = "run.json";
+
+/// File inside the run directory holding the event recording
+const EVENTS_FILE: &str = "events.jsonl";
+
+/// Settings a run was started with, needed to resume it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunMeta {
+    /// Unique id of the run, also the name of its directory
+    pub run_id: String,
+    /// OpenCode session the worker runs in, once created
+    pub session_id: Option<String>,
+    /// URL of the OpenCode server the run talked to
+    pub server_url: String,
+    /// Working directory of the worker
+    pub working_dir: PathBuf,
+    /// Model used by the worker
+    pub worker_model: String,
+    /// Reviewer backend
+    pub reviewer_backend: ReviewerBackend,
+    /// Reviewer API URL
+    pub reviewer_url: String,
+    /// Reviewer model
+    pub reviewer_model: String,
+    /// Reviewer system prompt override
+    #[serde(default)]
+    pub reviewer_system_prompt: Option<String>,
+    /// File holding the reviewer system prompt
+    #[serde(default)]
+    pub reviewer_system_prompt_file: Option<PathBuf>,
+    /// Review prompt template file
+    #[serde(default)]
+    pub reviewer_prompt_template: Option<PathBuf>,
+    /// Reviewer prompt size limit in estimated tokens
+    #[serde(default)]
+    pub reviewer_token_budget: Option<usize>,
+    /// Number of output lines sampled for each review
+    #[serde(default = "default_sample_lines")]
+    pub sample_lines: usize,
+    /// Control loop configuration
+    pub config: ControlConfig,
+    /// Git worktree the run works in, if isolated
+    #[serde(default)]
+    pub worktree: Option<Worktree>,
+    /// Whether the working tree is checkpointed after every review
+    #[serde(default)]
+    pub checkpoints: bool,
+    /// Whether session events are recorded for replay
+    #[serde(default)]
+    pub record_events: bool,
+    /// Commands that must pass before the run counts as completed
+    #[serde(default)]
+    pub verify: Vec<String>,
+    /// Seconds a verification command may run
+    #[serde(default = "default_verify_timeout")]
+    pub verify_timeout: u64,
+    /// When the run was created
+    pub created_at: DateTime<Utc>,
+}
+
+fn default_sample_lines() -> usize {
+    100
+}
+
+fn default_verify_timeout() -> u64 {
+    600
+}
+
+/// Borrowed view of a run, used for writing
+#[derive(Serialize)]
+struct RunRecordRef<'a> {
+    #[serde(flatten)]
+    meta: &'a RunMeta,
+    state: &'a State,
+}
+
+/// Owned run, used for reading
+#[derive(Deserialize)]
+struct RunRecord {
+    #[serde(flatten)]
+    meta: RunMeta,
+    state: State,
+}
+
+/// On-disk directory for a single run
+pub struct RunStore {
+    dir: PathBuf,
+}
+
+impl RunStore {
+    /// Create the directory for a new run
+    pub fn create(runs_dir: &Path, run_id: &str) -> Result<Self> {
+        let dir = runs_dir.join(run_id);
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create run directory {}", dir.display()))?;
+        ignore_dir(runs_dir)?;
+        Ok(Self { dir })
+    }
+
+    /// Open the directory of an existing run
+    pub fn open(runs_dir: &Path, run_id: &str) -> Result<Self> {
+        let dir = runs_dir.join(run_id);
+        if !dir.join(RUN_FILE).is_file() {
+            anyhow::bail!("No saved run '{}' in {}", run_id, runs_dir.display());
+        }
+        Ok(Self { dir })
+    }
+
+    /// The run directory
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Where the run's event recording is written
+    pub fn events_path(&self) -> PathBuf {
+        self.dir.join(EVENTS_FILE)
+    }
+
+    /// Write the run record, replacing the previous one atomically
+    pub fn save(&self, meta: &RunMeta, state: &State) -> Result<()> {
+        let record = RunRecordRef { meta, state };
+        let json = serde_json::to_string_pretty(&record).context("Failed to serialize run")?;
+
+        let path = self.dir.join(RUN_FILE);
+        let tmp_path = self.dir.join(format!("{}.tmp", RUN_FILE));
+        fs::write(&tmp_path, json)
+            .with_context(|| format!("Failed to write {}", tmp_path.display()))?;
+        fs::rename(&tmp_path, &path)
+            .with_context(|| format!("Failed to replace {}", path.display()))?;
+
+        debug!("Saved run state to {}", path.display());
+        Ok(())
+    }
+
+    /// Read the run record
+    pub fn load(&self) -> Result<(RunMeta, State)> {
+        let path = self.dir.join(RUN_FILE);
+        let json = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let record: RunRecord = serde_json::from_str(&json)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        Ok((record.meta, record.state))
+    }
+}
+
+/// Generate a run id from the current time and process id
+pub fn generate_run_id() -> String {
+    format!(
+        "{}-{}",
+        Utc::now().format("%Y%m%d-%H%M%S"),
+        std::process::id()
+    )
+}
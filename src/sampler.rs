@@ -1,7 +1,7 @@
 use opencode_rs::types::event::Event;
 use opencode_rs::types::message::Part;
 use std::collections::VecDeque;
-use tracing::trace;
+use tracing::{debug, trace};
 
 // Mock event type for testing
 #[derive(Debug, Clone)]
@@ -27,11 +27,53 @@ pub enum SamplerEvent {
     },
 }
 
+/// Most characters of a tool call's detail kept in its line
+const MAX_TOOL_DETAIL_CHARS: usize = 100;
+
+/// Input fields naming what a tool call works on, in order of preference
+const TOOL_DETAIL_FIELDS: [&str; 5] = ["filePath", "path", "command", "pattern", "url"];
+
+/// Line summarizing a tool call, e.g. `[Tool: edit src/main.rs]`, shared
+/// with session history so lines from the event stream and from history
+/// can be matched up
+pub fn tool_line(tool: &str, detail: Option<&str>) -> String {
+    match detail {
+        Some(detail) => format!("[Tool: {} {}]", tool, detail),
+        None => format!("[Tool: {}]", tool),
+    }
+}
+
+/// What a tool part worked on: the title OpenCode gave the call, or else
+/// its main input such as the file path
+///
+/// Without it, calls of the same tool on different files give identical
+/// lines and look like the worker repeating itself.
+pub fn tool_detail(part: &serde_json::Value) -> Option<String> {
+    let title = part.pointer("/state/title").and_then(|v| v.as_str());
+    let detail = title.filter(|title| !title.trim().is_empty()).or_else(|| {
+        let input = part.pointer("/state/input")?;
+        TOOL_DETAIL_FIELDS
+            .iter()
+            .find_map(|field| input.get(*field).and_then(|v| v.as_str()))
+    })?;
+    let detail: String = detail
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .chars()
+        .take(MAX_TOOL_DETAIL_CHARS)
+        .collect();
+    (!detail.is_empty()).then_some(detail)
+}
+
 /// Sampler that captures and buffers worker output
 /// Keeps only the last N lines for review
 pub struct Sampler {
     buffer: VecDeque<String>,
     max_lines: usize,
+    /// Most recent line added, kept across `clear` for backfilling
+    last_line: Option<String>,
 }
 
 impl Sampler {
@@ -40,6 +82,7 @@ impl Sampler {
         Self {
             buffer: VecDeque::with_capacity(max_lines),
             max_lines,
+            last_line: None,
         }
     }
 
@@ -49,10 +92,12 @@ impl Sampler {
         match event {
             // Capture text content from message part updates
             Event::MessagePartUpdated { properties } => {
-                // Capture text from part content
+                // Capture text and finished tool calls from part content
                 if let Some(ref part) = properties.part {
                     if let Part::Text { text, .. } = part {
                         self.add_lines(text);
+                    } else if let Some(line) = finished_tool(part) {
+                        self.add_line(&line);
                     }
                 }
                 // Capture delta updates
@@ -68,8 +113,7 @@ impl Sampler {
                 let props_str = match serde_json::to_string(properties) {
                     Ok(s) if !s.is_empty() => s,
                     _ => {
-                        let summary = format!("[Tool: unknown]");
-                        self.add_line(&summary);
+                        self.add_line(&tool_line("unknown", None));
                         return;
                     }
                 };
@@ -85,8 +129,7 @@ impl Sampler {
                 } else {
                     "unknown".to_string()
                 };
-                let summary = format!("[Tool: {}]", command_str);
-                self.add_line(&summary);
+                self.add_line(&tool_line(&command_str, None));
             }
 
             // Capture error events
@@ -145,7 +188,33 @@ impl Sampler {
                 self.buffer.pop_front();
             }
             self.buffer.push_back(trimmed.to_string());
+            self.last_line = Some(trimmed.to_string());
+        }
+    }
+
+    /// Add the lines of `history` that come after the last line seen
+    ///
+    /// If the last line can't be found in `history`, only its last
+    /// `max_lines` lines are added rather than the whole session.
+    /// Returns how many lines were added
+    pub fn backfill(&mut self, history: &[String]) -> usize {
+        let tail = history.len().saturating_sub(self.max_lines);
+        let start = match self.last_line {
+            Some(ref last) => match history.iter().rposition(|line| line.trim() == last) {
+                Some(index) => index + 1,
+                None => {
+                    debug!("Last sampled line not in history, backfilling its tail");
+                    tail
+                }
+            },
+            None => 0,
+        };
+
+        let missed = &history[start..];
+        for line in missed {
+            self.add_line(line);
         }
+        missed.len()
     }
 
     /// Test-only method to process SamplerEvent for unit tests
@@ -179,3 +248,23 @@ impl Sampler {
         }
     }
 }
+
+/// Line for a tool part that has finished running
+///
+/// Running tools update their part several times, only the final update is
+/// sampled so each call shows up once.
+fn finished_tool(part: &Part) -> Option<String> {
+    let part = serde_json::to_value(part).ok()?;
+    if part.get("type").and_then(|v| v.as_str()) != Some("tool") {
+        return None;
+    }
+    let status = part.pointer("/state/status").and_then(|v| v.as_str());
+    if !matches!(status, Some("completed") | Some("error")) {
+        return None;
+    }
+    let tool = part
+        .get("tool")
+        .and_then(|v| v.as_str())
+        .unwrap_or("unknown");
+    Some(tool_line(tool, tool_detail(&part).as_deref()))
+}
@@ -6,7 +6,8 @@ use tokio::time::{timeout, Duration};
 use tracing::{debug, error, info, warn};
 
 pub struct ServerManager {
-    process: Child,
+    /// The spawned `opencode serve` process, `None` when attached to an existing server
+    process: Option<Child>,
     port: u16,
     base_url: String,
 }
@@ -17,10 +18,26 @@ impl ServerManager {
         working_dir: &Path,
         model: &str,
         extra_args: &[String],
+    ) -> Result<Self> {
+        Self::spawn_with(Path::new("opencode"), None, working_dir, model, extra_args).await
+    }
+
+    /// Spawn `program serve`, on `port` or a random available one
+    ///
+    /// Lets tests stand in a fake binary for `opencode` and point the
+    /// health check at a mock server already listening on `port`.
+    pub async fn spawn_with(
+        program: &Path,
+        port: Option<u16>,
+        working_dir: &Path,
+        model: &str,
+        extra_args: &[String],
     ) -> Result<Self> {
         // Find an available port
-        let port = portpicker::pick_unused_port()
-            .context("Failed to find an available port")?;
+        let port = match port {
+            Some(port) => port,
+            None => portpicker::pick_unused_port().context("Failed to find an available port")?,
+        };
 
         info!("Spawning opencode serve on port {}", port);
 
@@ -38,16 +55,22 @@ impl ServerManager {
         // Add extra arguments passed by user
         args.extend(extra_args.iter().cloned());
 
-        debug!("Command: opencode {}", args.join(" "));
+        debug!("Command: {} {}", program.display(), args.join(" "));
 
         // Spawn the process
-        let mut process = Command::new("opencode")
+        let mut process = Command::new(program)
             .args(&args)
             .current_dir(working_dir)
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
+            .kill_on_drop(true)
             .spawn()
-            .context("Failed to spawn opencode serve. Is opencode installed and in PATH?")?;
+            .with_context(|| {
+                format!(
+                    "Failed to spawn {} serve. Is opencode installed and in PATH?",
+                    program.display()
+                )
+            })?;
 
         // Wait a moment for the server to start
         tokio::time::sleep(Duration::from_millis(1000)).await;
@@ -113,12 +136,36 @@ impl ServerManager {
         }
 
         Ok(Self {
-            process,
+            process: Some(process),
             port,
             base_url,
         })
     }
 
+    /// Use an already-running OpenCode server
+    ///
+    /// The server is not owned by the runner and is left running on shutdown.
+    pub fn attach(base_url: &str) -> Result<Self> {
+        let url = reqwest::Url::parse(base_url)
+            .with_context(|| format!("Invalid OpenCode server URL: {}", base_url))?;
+        let port = url
+            .port_or_known_default()
+            .with_context(|| format!("OpenCode server URL has no port: {}", base_url))?;
+
+        info!("Using existing OpenCode server at {}", base_url);
+
+        Ok(Self {
+            process: None,
+            port,
+            base_url: base_url.trim_end_matches('/').to_string(),
+        })
+    }
+
+    /// Whether the server process was spawned (and will be killed) by the runner
+    pub fn is_owned(&self) -> bool {
+        self.process.is_some()
+    }
+
     pub fn port(&self) -> u16 {
         self.port
     }
@@ -129,10 +176,18 @@ impl ServerManager {
 
     /// Gracefully shutdown the server
     pub async fn shutdown(mut self) -> Result<()> {
+        let Some(ref mut process) = self.process else {
+            info!(
+                "Leaving attached OpenCode server at {} running",
+                self.base_url
+            );
+            return Ok(());
+        };
+
         info!("Shutting down OpenCode server...");
 
         // Try graceful shutdown first
-        match self.process.kill().await {
+        match process.kill().await {
             Ok(()) => {
                 info!("OpenCode server terminated");
             }
@@ -142,7 +197,7 @@ impl ServerManager {
         }
 
         // Wait for process to exit
-        match timeout(Duration::from_secs(5), self.process.wait()).await {
+        match timeout(Duration::from_secs(5), process.wait()).await {
             Ok(Ok(status)) => {
                 debug!("OpenCode server exited with status: {}", status);
             }
@@ -161,9 +216,14 @@ impl ServerManager {
 impl Drop for ServerManager {
     fn drop(&mut self) {
         // Best effort cleanup
-        if let Some(id) = self.process.id() {
-            debug!("Cleaning up OpenCode server process {}", id);
-            // Note: Can't use async in drop, so we can't wait for it
+        if let Some(ref mut process) = self.process {
+            if let Some(id) = process.id() {
+                debug!("Cleaning up OpenCode server process {}", id);
+                // Note: Can't use async in drop, so we can't wait for it
+                if let Err(e) = process.start_kill() {
+                    warn!("Failed to kill OpenCode server process {}: {}", id, e);
+                }
+            }
         }
     }
 }
@@ -1,7 +1,11 @@
 use crate::reviewer::{ReviewerAction, ReviewerDecision};
+use crate::verify::VerificationResult;
 use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 /// Tracks the state of a control loop run
+#[derive(Serialize, Deserialize)]
 pub struct State {
     /// All completed iterations
     iterations: Vec<Iteration>,
@@ -9,9 +13,74 @@ pub struct State {
     current_iteration: usize,
     /// When the run started
     start_time: DateTime<Utc>,
+    /// Latest token usage of each worker message, by message id
+    #[serde(default)]
+    message_usage: BTreeMap<String, TokenUsage>,
+    /// Checkpoint of the working tree before the worker started
+    #[serde(default)]
+    baseline_checkpoint: Option<String>,
+}
+
+/// Token and cost totals reported by the worker
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct TokenUsage {
+    pub input: u64,
+    pub output: u64,
+    pub reasoning: u64,
+    pub cache_read: u64,
+    pub cache_write: u64,
+    /// Cost in USD as reported by OpenCode
+    pub cost: f64,
+}
+
+impl TokenUsage {
+    /// Parse the usage of an assistant message from its `info` object
+    ///
+    /// Returns the message id and its usage, or `None` for messages
+    /// without usage (e.g. user messages).
+    pub fn from_message_info(info: &serde_json::Value) -> Option<(String, Self)> {
+        if info.get("role").and_then(|r| r.as_str()) != Some("assistant") {
+            return None;
+        }
+        let id = info.get("id")?.as_str()?.to_string();
+        let tokens = info.get("tokens")?;
+        let count = |pointer: &str| {
+            tokens
+                .pointer(pointer)
+                .and_then(|v| v.as_u64())
+                .unwrap_or(0)
+        };
+
+        let usage = Self {
+            input: count("/input"),
+            output: count("/output"),
+            reasoning: count("/reasoning"),
+            cache_read: count("/cache/read"),
+            cache_write: count("/cache/write"),
+            cost: info.get("cost").and_then(|v| v.as_f64()).unwrap_or(0.0),
+        };
+        Some((id, usage))
+    }
+
+    /// Total tokens across all categories
+    pub fn total_tokens(&self) -> u64 {
+        self.input + self.output + self.reasoning + self.cache_read + self.cache_write
+    }
+}
+
+impl std::ops::AddAssign for TokenUsage {
+    fn add_assign(&mut self, other: Self) {
+        self.input += other.input;
+        self.output += other.output;
+        self.reasoning += other.reasoning;
+        self.cache_read += other.cache_read;
+        self.cache_write += other.cache_write;
+        self.cost += other.cost;
+    }
 }
 
 /// Record of a single iteration
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Iteration {
     /// Iteration number
     pub number: usize,
@@ -23,6 +92,12 @@ pub struct Iteration {
     pub decision: ReviewerDecision,
     /// How many retries were needed for the reviewer
     pub reviewer_retry_count: u8,
+    /// Checkpoint commit of the working tree taken after the decision
+    #[serde(default)]
+    pub checkpoint: Option<String>,
+    /// Verification commands run after the worker finished this iteration
+    #[serde(default)]
+    pub verification: Vec<VerificationResult>,
 }
 
 impl State {
@@ -32,6 +107,8 @@ impl State {
             iterations: Vec::new(),
             current_iteration: 0,
             start_time: Utc::now(),
+            message_usage: BTreeMap::new(),
+            baseline_checkpoint: None,
         }
     }
 
@@ -58,10 +135,45 @@ impl State {
             sample_size,
             decision,
             reviewer_retry_count: retry_count,
+            checkpoint: None,
+            verification: Vec::new(),
         };
         self.iterations.push(iteration);
     }
 
+    /// Attach a checkpoint commit to the last recorded iteration
+    pub fn record_checkpoint(&mut self, commit: String) {
+        if let Some(iter) = self.iterations.last_mut() {
+            iter.checkpoint = Some(commit);
+        }
+    }
+
+    /// Attach verification results to the last recorded iteration
+    pub fn record_verification(&mut self, results: Vec<VerificationResult>) {
+        if let Some(iter) = self.iterations.last_mut() {
+            iter.verification = results;
+        }
+    }
+
+    /// Record the checkpoint taken before the worker started
+    pub fn record_baseline_checkpoint(&mut self, commit: String) {
+        self.baseline_checkpoint = Some(commit);
+    }
+
+    /// The checkpoint taken before the worker started, if any
+    pub fn baseline_checkpoint(&self) -> Option<&str> {
+        self.baseline_checkpoint.as_deref()
+    }
+
+    /// The most recent checkpoint the reviewer approved with Continue
+    pub fn last_continue_checkpoint(&self) -> Option<&str> {
+        self.iterations
+            .iter()
+            .rev()
+            .filter(|iter| iter.decision.action == ReviewerAction::Continue)
+            .find_map(|iter| iter.checkpoint.as_deref())
+    }
+
     /// Get summaries of previous iterations for reviewer context
     pub fn get_previous_summaries(&self, count: usize) -> Vec<String> {
         self.iterations
@@ -114,20 +226,20 @@ impl State {
         self.iterations
             .iter()
             .map(|iter| {
-                let action_str = match iter.decision.action {
-                    ReviewerAction::Continue => "✓ Continue",
-                    ReviewerAction::Abort => "✗ Abort",
-                };
-                format!(
+                let mut line = format!(
                     "[{}] Iter {}/{}: {} - {} ({} lines, {} retries)",
                     iter.timestamp.format("%H:%M:%S"),
                     iter.number,
                     self.current_iteration,
-                    action_str,
+                    action_label(&iter.decision.action),
                     iter.decision.reason,
                     iter.sample_size,
                     iter.reviewer_retry_count
-                )
+                );
+                for result in &iter.verification {
+                    line.push_str(&format!("\n    verify: {}", result.summary()));
+                }
+                line
             })
             .collect::<Vec<_>>()
             .join("\n")
@@ -149,14 +261,50 @@ impl State {
                 "Iteration {} - Abort: {}",
                 self.current_iteration, last.decision.reason
             ),
+            ReviewerAction::Redirect { ref guidance } => format!(
+                "Iteration {} - Redirected: {}",
+                self.current_iteration, guidance
+            ),
+            ReviewerAction::Complete => format!(
+                "Iteration {} - Complete: {}",
+                self.current_iteration, last.decision.reason
+            ),
         }
     }
 
+    /// Get the guidance sent to the worker, in order
+    pub fn redirects(&self) -> Vec<(usize, &str)> {
+        self.iterations
+            .iter()
+            .filter_map(|iter| match iter.decision.action {
+                ReviewerAction::Redirect { ref guidance } => Some((iter.number, guidance.as_str())),
+                _ => None,
+            })
+            .collect()
+    }
+
     /// Count total lines sampled across all iterations
     pub fn total_lines_sampled(&self) -> usize {
         self.iterations.iter().map(|i| i.sample_size).sum()
     }
 
+    /// Record the latest usage of a worker message
+    ///
+    /// OpenCode re-sends a message's running totals as it streams, so the
+    /// newest value replaces any earlier one.
+    pub fn record_message_usage(&mut self, message_id: String, usage: TokenUsage) {
+        self.message_usage.insert(message_id, usage);
+    }
+
+    /// Token and cost totals across all worker messages
+    pub fn total_usage(&self) -> TokenUsage {
+        let mut total = TokenUsage::default();
+        for usage in self.message_usage.values() {
+            total += *usage;
+        }
+        total
+    }
+
     /// Count total retries
     pub fn total_retries(&self) -> u32 {
         self.iterations
@@ -166,6 +314,16 @@ impl State {
     }
 }
 
+/// Short label for a reviewer action, used in logs and reports
+pub fn action_label(action: &ReviewerAction) -> &'static str {
+    match action {
+        ReviewerAction::Continue => "✓ Continue",
+        ReviewerAction::Abort => "✗ Abort",
+        ReviewerAction::Redirect { .. } => "↪ Redirect",
+        ReviewerAction::Complete => "★ Complete",
+    }
+}
+
 impl Default for State {
     fn default() -> Self {
         Self::new()
@@ -206,6 +364,98 @@ mod tests {
         assert_eq!(summaries.len(), 1);
     }
 
+    #[test]
+    fn test_redirects_recorded() {
+        let mut state = State::new();
+
+        state.start_iteration();
+        state.record_decision(
+            10,
+            ReviewerDecision {
+                action: ReviewerAction::Continue,
+                reason: "Good progress".to_string(),
+            },
+            0,
+        );
+        state.start_iteration();
+        state.record_decision(
+            20,
+            ReviewerDecision {
+                action: ReviewerAction::Redirect {
+                    guidance: "Run the tests before refactoring".to_string(),
+                },
+                reason: "Skipping verification".to_string(),
+            },
+            0,
+        );
+
+        assert_eq!(
+            state.redirects(),
+            vec![(2, "Run the tests before refactoring")]
+        );
+        assert!(state.format_activity_log().contains("↪ Redirect"));
+        assert!(state.status_summary().contains("Redirected"));
+    }
+
+    #[test]
+    fn test_message_usage_totals() {
+        let mut state = State::new();
+        let info = serde_json::json!({
+            "id": "msg_1",
+            "role": "assistant",
+            "tokens": {"input": 100, "output": 20, "reasoning": 0, "cache": {"read": 50, "write": 0}},
+            "cost": 0.01
+        });
+
+        let (id, usage) = TokenUsage::from_message_info(&info).unwrap();
+        state.record_message_usage(id.clone(), usage);
+        // A later update for the same message replaces the earlier totals
+        state.record_message_usage(id, usage);
+        state.record_message_usage(
+            "msg_2".to_string(),
+            TokenUsage {
+                output: 5,
+                ..Default::default()
+            },
+        );
+
+        let total = state.total_usage();
+        assert_eq!(total.input, 100);
+        assert_eq!(total.output, 25);
+        assert_eq!(total.cache_read, 50);
+        assert_eq!(total.total_tokens(), 175);
+        assert!((total.cost - 0.01).abs() < f64::EPSILON);
+
+        let user = serde_json::json!({"id": "msg_0", "role": "user"});
+        assert!(TokenUsage::from_message_info(&user).is_none());
+    }
+
+    #[test]
+    fn test_last_continue_checkpoint() {
+        let mut state = State::new();
+        assert_eq!(state.last_continue_checkpoint(), None);
+
+        for (action, commit) in [
+            (ReviewerAction::Continue, "aaa"),
+            (ReviewerAction::Continue, "bbb"),
+            (ReviewerAction::Abort, "ccc"),
+        ] {
+            state.start_iteration();
+            state.record_decision(
+                10,
+                ReviewerDecision {
+                    action,
+                    reason: String::new(),
+                },
+                0,
+            );
+            state.record_checkpoint(commit.to_string());
+        }
+
+        assert_eq!(state.last_continue_checkpoint(), Some("bbb"));
+        assert_eq!(state.iterations()[2].checkpoint.as_deref(), Some("ccc"));
+    }
+
     #[test]
     fn test_max_iterations() {
         let mut state = State::new();
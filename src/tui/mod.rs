@@ -204,6 +204,8 @@ pub async fn process_ui_event(event: UiEvent, state: &mut UiState) {
             let action_str = match decision.action {
                 ReviewerAction::Continue => "Continue",
                 ReviewerAction::Abort => "Abort",
+                ReviewerAction::Redirect { .. } => "Redirect",
+                ReviewerAction::Complete => "Complete",
             };
             state.add_activity(format!(
                 "[{}] {}: {}",
@@ -215,6 +217,13 @@ pub async fn process_ui_event(event: UiEvent, state: &mut UiState) {
             // Also update status
             state.set_status(format!("{}: {}", action_str, decision.reason));
         }
+        UiEvent::GuidanceSent(guidance) => {
+            state.add_activity(format!(
+                "[{}] Guidance sent: {}",
+                chrono::Local::now().format("%H:%M:%S"),
+                guidance
+            ));
+        }
         UiEvent::StatusUpdate(status) => {
             state.set_status(status);
         }
@@ -308,8 +317,10 @@ fn draw_activity_log(f: &mut ratatui::Frame, area: ratatui::layout::Rect, state:
             .map(|entry| {
                 let style = if entry.contains("Abort") {
                     Style::default().fg(Color::Red)
-                } else if entry.contains("Continue") {
+                } else if entry.contains("Continue") || entry.contains("Complete") {
                     Style::default().fg(Color::Green)
+                } else if entry.contains("Redirect") || entry.contains("Guidance") {
+                    Style::default().fg(Color::Yellow)
                 } else {
                     Style::default()
                 };
@@ -0,0 +1,216 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+use tracing::{info, warn};
+
+/// Most lines of command output kept in a result
+const MAX_OUTPUT_LINES: usize = 60;
+
+/// Most bytes of command output kept in a result
+const MAX_OUTPUT_BYTES: usize = 4000;
+
+/// How long a command may run unless configured otherwise
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Outcome of one verification command
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VerificationResult {
+    /// Command as configured, run through `sh -c`
+    pub command: String,
+    /// Whether the command exited successfully
+    pub success: bool,
+    /// Exit code, `None` if the command could not be run or was killed
+    pub exit_code: Option<i32>,
+    /// Tail of the combined stdout and stderr
+    pub output: String,
+    /// How long the command took in milliseconds
+    pub duration_ms: u64,
+}
+
+impl VerificationResult {
+    /// One-line description, e.g. "`cargo test` failed (exit 101)"
+    pub fn summary(&self) -> String {
+        match (self.success, self.exit_code) {
+            (true, _) => format!("`{}` passed", self.command),
+            (false, Some(code)) => format!("`{}` failed (exit {})", self.command, code),
+            (false, None) => format!("`{}` failed", self.command),
+        }
+    }
+}
+
+/// Runs the commands that must pass before a task counts as done
+#[derive(Debug, Clone)]
+pub struct Verifier {
+    working_dir: PathBuf,
+    commands: Vec<String>,
+    timeout: Duration,
+}
+
+impl Verifier {
+    /// Verifier running `commands` in `working_dir`
+    pub fn new(working_dir: PathBuf, commands: Vec<String>) -> Self {
+        Self {
+            working_dir,
+            commands,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+
+    /// Kill each command that runs longer than `timeout` and count it as failed
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn commands(&self) -> &[String] {
+        &self.commands
+    }
+
+    /// Run the commands in order, stopping at the first failure
+    pub async fn run(&self) -> Vec<VerificationResult> {
+        let mut results = Vec::new();
+        for command in &self.commands {
+            let result = self.run_command(command).await;
+            let passed = result.success;
+            info!("Verification: {}", result.summary());
+            results.push(result);
+            if !passed {
+                break;
+            }
+        }
+        results
+    }
+
+    async fn run_command(&self, command: &str) -> VerificationResult {
+        let start = Instant::now();
+        let mut process = Command::new("sh");
+        process
+            .arg("-c")
+            .arg(command)
+            .current_dir(&self.working_dir)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        // Own process group, so a timeout can kill everything the shell started
+        #[cfg(unix)]
+        process.process_group(0);
+
+        let output = match process.spawn() {
+            Ok(child) => {
+                let pid = child.id();
+                let output = tokio::time::timeout(self.timeout, child.wait_with_output()).await;
+                if output.is_err() {
+                    kill_process_group(pid);
+                }
+                output
+            }
+            Err(e) => Ok(Err(e)),
+        };
+        let duration_ms = start.elapsed().as_millis() as u64;
+
+        match output {
+            Err(_) => {
+                warn!(
+                    "Verification command `{}` timed out after {:?}",
+                    command, self.timeout
+                );
+                VerificationResult {
+                    command: command.to_string(),
+                    success: false,
+                    exit_code: None,
+                    output: format!("Timed out after {:?}", self.timeout),
+                    duration_ms,
+                }
+            }
+            Ok(Ok(output)) => {
+                let mut combined = String::from_utf8_lossy(&output.stdout).into_owned();
+                combined.push_str(&String::from_utf8_lossy(&output.stderr));
+                VerificationResult {
+                    command: command.to_string(),
+                    success: output.status.success(),
+                    exit_code: output.status.code(),
+                    output: trim_output(&combined),
+                    duration_ms,
+                }
+            }
+            Ok(Err(e)) => {
+                warn!("Failed to run verification command `{}`: {}", command, e);
+                VerificationResult {
+                    command: command.to_string(),
+                    success: false,
+                    exit_code: None,
+                    output: format!("Failed to run command: {}", e),
+                    duration_ms,
+                }
+            }
+        }
+    }
+}
+
+/// Kill the process group led by `pid`, including the shell's children
+#[cfg(unix)]
+fn kill_process_group(pid: Option<u32>) {
+    let Some(pid) = pid else { return };
+    // SAFETY: killpg only sends a signal; the group id is our child's pid
+    if unsafe { libc::killpg(pid as libc::pid_t, libc::SIGKILL) } != 0 {
+        warn!(
+            "Failed to kill verification process group {}: {}",
+            pid,
+            std::io::Error::last_os_error()
+        );
+    }
+}
+
+/// Without process groups only the shell is killed, when its child is dropped
+#[cfg(not(unix))]
+fn kill_process_group(_pid: Option<u32>) {}
+
+/// Whether every command passed
+pub fn all_passed(results: &[VerificationResult]) -> bool {
+    results.iter().all(|result| result.success)
+}
+
+/// Message sent to the worker when verification fails
+pub fn failure_message(results: &[VerificationResult]) -> String {
+    let mut message = String::from(
+        "The task is not done yet: verification failed. Fix the problems below, then finish again.\n",
+    );
+    for result in results.iter().filter(|result| !result.success) {
+        message.push_str(&format!(
+            "\n{}:\n```\n{}\n```\n",
+            result.summary(),
+            result.output
+        ));
+    }
+    message
+}
+
+/// Keep the end of the output, where errors usually are
+///
+/// Limited to `MAX_OUTPUT_LINES` lines and `MAX_OUTPUT_BYTES` bytes, with a
+/// marker at the top when anything was dropped.
+pub fn trim_output(output: &str) -> String {
+    let output = output.trim();
+    let lines: Vec<&str> = output.lines().collect();
+    let skipped = lines.len().saturating_sub(MAX_OUTPUT_LINES);
+    let mut kept = lines[skipped..].join("\n");
+    let mut truncated = skipped > 0;
+
+    if kept.len() > MAX_OUTPUT_BYTES {
+        let mut start = kept.len() - MAX_OUTPUT_BYTES;
+        while !kept.is_char_boundary(start) {
+            start += 1;
+        }
+        kept = kept[start..].to_string();
+        truncated = true;
+    }
+
+    if truncated {
+        format!("... (output truncated)\n{}", kept)
+    } else {
+        kept
+    }
+}
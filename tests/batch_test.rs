@@ -0,0 +1,155 @@
+use opencode_runner::batch::{load_tasks, parse_jsonl, parse_yaml, BatchError, BatchReport};
+use opencode_runner::{BatchTask, ControlConfig, RunReport, RunResult, State};
+use std::time::Duration;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(result: RunResult) -> RunReport {
+        let config = ControlConfig::new("Task".to_string(), 10, Duration::from_secs(30));
+        RunReport::new(&config, None, &State::new(), &result)
+    }
+
+    #[test]
+    fn test_parse_yaml_list() {
+        let yaml = r#"
+- Fix the failing test in parser.rs
+- task: Add a --verbose flag
+  max_iterations: 5
+  inactivity_timeout: 60
+"#;
+        let tasks = parse_yaml(yaml).unwrap();
+
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].task, "Fix the failing test in parser.rs");
+        assert_eq!(tasks[0].max_iterations, None);
+        assert_eq!(
+            tasks[1],
+            BatchTask {
+                task: "Add a --verbose flag".to_string(),
+                max_iterations: Some(5),
+                inactivity_timeout: Some(60),
+                verify: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_yaml_tasks_key() {
+        let yaml = "tasks:\n  - First\n  - Second\n";
+        let tasks = parse_yaml(yaml).unwrap();
+
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[1].task, "Second");
+    }
+
+    #[test]
+    fn test_parse_jsonl() {
+        let jsonl = r#"{"task": "First", "max_iterations": 3, "verify": ["cargo test"]}
+
+"Second"
+"#;
+        let tasks = parse_jsonl(jsonl).unwrap();
+
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].max_iterations, Some(3));
+        assert_eq!(tasks[0].verify, Some(vec!["cargo test".to_string()]));
+        assert_eq!(tasks[1].task, "Second");
+        assert_eq!(tasks[1].verify, None);
+    }
+
+    #[test]
+    fn test_parse_jsonl_reports_bad_line() {
+        let err = parse_jsonl("\"First\"\n{\"tsk\": \"typo\"}\n").unwrap_err();
+        assert!(err.to_string().contains("line 2"));
+    }
+
+    #[test]
+    fn test_empty_task_rejected() {
+        assert!(parse_yaml("- \"\"\n").is_err());
+    }
+
+    #[test]
+    fn test_load_tasks_by_extension() {
+        let dir = tempfile::tempdir().unwrap();
+        let jsonl = dir.path().join("tasks.jsonl");
+        std::fs::write(&jsonl, "\"One\"\n\"Two\"\n").unwrap();
+        let yaml = dir.path().join("tasks.yaml");
+        std::fs::write(&yaml, "- One\n").unwrap();
+        let empty = dir.path().join("empty.yaml");
+        std::fs::write(&empty, "[]\n").unwrap();
+
+        assert_eq!(load_tasks(&jsonl).unwrap().len(), 2);
+        assert_eq!(load_tasks(&yaml).unwrap().len(), 1);
+        assert!(load_tasks(&empty).is_err());
+    }
+
+    #[test]
+    fn test_batch_report_counts() {
+        let runs = vec![
+            report(RunResult::Completed),
+            report(RunResult::Completed),
+            report(RunResult::Aborted("Stuck".to_string())),
+            report(RunResult::MaxIterations),
+        ];
+        let errors = vec![BatchError {
+            task: "Broken".to_string(),
+            error: "Failed to create session".to_string(),
+        }];
+
+        let batch = BatchReport::new(6, runs, errors);
+
+        assert_eq!(batch.completed, 2);
+        assert_eq!(batch.aborted, 1);
+        assert_eq!(batch.max_iterations, 1);
+        assert_eq!(batch.errored, 1);
+        assert_eq!(batch.skipped, 1);
+        assert!(!batch.all_completed());
+        assert_eq!(
+            batch.format_summary(),
+            "6 tasks: 2 completed, 1 aborted, 1 max iterations, 1 errored, 1 skipped"
+        );
+    }
+
+    #[test]
+    fn test_batch_report_exports() {
+        let batch = BatchReport::new(
+            2,
+            vec![
+                report(RunResult::Completed),
+                report(RunResult::Aborted("Stuck | looping".to_string())),
+            ],
+            vec![],
+        );
+
+        let markdown = batch.to_markdown();
+        assert!(markdown.contains("| 1 | Task | completed | 0 | 0s |  |"));
+        assert!(markdown.contains("| 2 | Task | aborted | 0 | 0s | Stuck \\| looping |"));
+
+        let json: serde_json::Value = serde_json::from_str(&batch.to_json().unwrap()).unwrap();
+        assert_eq!(json["completed"], 1);
+        assert_eq!(json["runs"].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_batch_junit_reports_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let batch = BatchReport::new(
+            2,
+            vec![report(RunResult::Completed)],
+            vec![BatchError {
+                task: "Broken".to_string(),
+                error: "Failed to start <server>".to_string(),
+            }],
+        );
+
+        let path = dir.path().join("junit.xml");
+        batch.write_junit(&path).unwrap();
+        let xml = std::fs::read_to_string(&path).unwrap();
+
+        assert!(xml.contains("tests=\"2\" failures=\"0\" errors=\"1\""));
+        assert!(xml.contains("<testcase classname=\"opencode_runner\" name=\"Broken\""));
+        assert!(xml.contains("<error message=\"Failed to start &lt;server&gt;\">"));
+    }
+}
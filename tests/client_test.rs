@@ -0,0 +1,89 @@
+use opencode_runner::client::SessionHistory;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_history_from_messages() {
+        let messages = serde_json::json!([
+            {
+                "info": {"id": "msg_1", "role": "user"},
+                "parts": [{"type": "text", "text": "Add a --verbose flag"}]
+            },
+            {
+                "info": {"id": "msg_2", "role": "assistant"},
+                "parts": [
+                    {"type": "text", "text": "Looking at the CLI parser.\n\nFound it."},
+                    {"type": "tool", "tool": "read", "state": {"status": "completed"}},
+                    {"type": "tool", "tool": "edit", "state": {
+                        "status": "completed",
+                        "title": "src/cli.rs",
+                        "input": {"filePath": "/repo/src/cli.rs"}
+                    }},
+                    {"type": "tool", "tool": "bash", "state": {
+                        "status": "completed",
+                        "input": {"command": "cargo test"}
+                    }},
+                    {"type": "reasoning", "text": "hidden thoughts"}
+                ]
+            },
+            {
+                "info": {"id": "msg_3", "role": "user"},
+                "parts": [{"type": "text", "text": "Also update the README"}]
+            }
+        ]);
+
+        let history = SessionHistory::from_messages(&messages);
+
+        assert_eq!(
+            history.first_prompt.as_deref(),
+            Some("Add a --verbose flag")
+        );
+        assert_eq!(
+            history.lines,
+            vec![
+                "Add a --verbose flag",
+                "Looking at the CLI parser.",
+                "Found it.",
+                "[Tool: read]",
+                "[Tool: edit src/cli.rs]",
+                "[Tool: bash cargo test]",
+                "Also update the README",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_history_from_empty_session() {
+        let history = SessionHistory::from_messages(&serde_json::json!([]));
+
+        assert!(history.first_prompt.is_none());
+        assert!(history.lines.is_empty());
+        assert!(!history.busy);
+    }
+
+    #[test]
+    fn test_history_busy_until_reply_completes() {
+        let mut messages = serde_json::json!([
+            {
+                "info": {"id": "msg_1", "role": "user"},
+                "parts": [{"type": "text", "text": "Add a --verbose flag"}]
+            },
+            {
+                "info": {"id": "msg_2", "role": "assistant", "time": {"created": 1}},
+                "parts": [{"type": "text", "text": "Looking at the CLI parser."}]
+            }
+        ]);
+        assert!(SessionHistory::from_messages(&messages).busy);
+
+        messages[1]["info"]["time"]["completed"] = serde_json::json!(2);
+        assert!(!SessionHistory::from_messages(&messages).busy);
+    }
+
+    #[test]
+    fn test_history_ignores_unexpected_shapes() {
+        let history = SessionHistory::from_messages(&serde_json::json!({"error": "nope"}));
+        assert!(history.lines.is_empty());
+    }
+}
@@ -0,0 +1,167 @@
+use opencode_runner::config::{ConfigFile, ConfigLayer, RunnerConfig};
+use opencode_runner::ReviewerBackend;
+use std::path::PathBuf;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROJECT_FILE: &str = r#"
+default_profile = "local"
+
+[defaults]
+max_iterations = 20
+worker_model = "ollama/qwen2.5-coder"
+
+[profiles.local]
+reviewer_url = "http://localhost:11434/v1"
+reviewer_model = "llama3"
+
+[profiles.cloud]
+reviewer_backend = "anthropic"
+reviewer_url = "https://api.anthropic.com/v1"
+reviewer_model = "claude-sonnet"
+inactivity_timeout = 60
+reviewer_system_prompt = "Be strict."
+"#;
+
+    #[test]
+    fn test_parse_config_file() {
+        let file = ConfigFile::parse(PROJECT_FILE).unwrap();
+
+        assert_eq!(file.default_profile.as_deref(), Some("local"));
+        assert_eq!(file.defaults.max_iterations, Some(20));
+        assert_eq!(file.profiles.len(), 2);
+        assert_eq!(
+            file.profiles["cloud"].reviewer_backend,
+            Some(ReviewerBackend::Anthropic)
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_unknown_keys() {
+        assert!(ConfigFile::parse("[defaults]\nmax_iteration = 5\n").is_err());
+    }
+
+    #[test]
+    fn test_profile_overrides_defaults() {
+        let file = ConfigFile::parse(PROJECT_FILE).unwrap();
+        let layer = file.resolve(Some("cloud"));
+
+        assert_eq!(layer.max_iterations, Some(20));
+        assert_eq!(layer.inactivity_timeout, Some(60));
+        assert_eq!(layer.reviewer_model.as_deref(), Some("claude-sonnet"));
+    }
+
+    #[test]
+    fn test_defaults_without_layers() {
+        let config =
+            RunnerConfig::from_layers(&[], None, &ConfigLayer::default(), &ConfigLayer::default())
+                .unwrap();
+
+        assert_eq!(config, RunnerConfig::default());
+        assert_eq!(config.max_iterations, 10);
+        assert_eq!(config.reviewer_backend, ReviewerBackend::OpenAi);
+    }
+
+    #[test]
+    fn test_layering_file_env_cli() {
+        let file = ConfigFile::parse(PROJECT_FILE).unwrap();
+        let env = ConfigLayer {
+            max_iterations: Some(30),
+            reviewer_model: Some("env-model".to_string()),
+            verify_timeout: Some(120),
+            ..Default::default()
+        };
+        let cli = ConfigLayer {
+            max_iterations: Some(40),
+            headless: Some(true),
+            ..Default::default()
+        };
+
+        let config = RunnerConfig::from_layers(&[file], Some("cloud"), &env, &cli).unwrap();
+
+        assert_eq!(config.max_iterations, 40);
+        assert_eq!(config.reviewer_model, "env-model");
+        assert_eq!(config.verify_timeout, 120);
+        assert_eq!(config.inactivity_timeout, 60);
+        assert_eq!(config.worker_model, "ollama/qwen2.5-coder");
+        assert_eq!(config.reviewer_system_prompt.as_deref(), Some("Be strict."));
+        assert!(config.headless);
+    }
+
+    #[test]
+    fn test_project_file_overrides_user_file() {
+        let user =
+            ConfigFile::parse("[defaults]\nruns_dir = \"/home/me/runs\"\nmax_iterations = 5\n")
+                .unwrap();
+        let project = ConfigFile::parse("[defaults]\nmax_iterations = 8\n").unwrap();
+
+        let config = RunnerConfig::from_layers(
+            &[user, project],
+            None,
+            &ConfigLayer::default(),
+            &ConfigLayer::default(),
+        )
+        .unwrap();
+
+        assert_eq!(config.max_iterations, 8);
+        assert_eq!(config.runs_dir, PathBuf::from("/home/me/runs"));
+    }
+
+    #[test]
+    fn test_unknown_profile_is_an_error() {
+        let file = ConfigFile::parse(PROJECT_FILE).unwrap();
+        let result = RunnerConfig::from_layers(
+            &[file],
+            Some("missing"),
+            &ConfigLayer::default(),
+            &ConfigLayer::default(),
+        );
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_control_config_from_runner_config() {
+        let config = RunnerConfig {
+            max_iterations: 3,
+            inactivity_timeout: 15,
+            ..Default::default()
+        };
+
+        let control = config.control_config("Fix the build").unwrap();
+        assert_eq!(control.task, "Fix the build");
+        assert_eq!(control.max_iterations, 3);
+        assert_eq!(control.inactivity_timeout.as_secs(), 15);
+    }
+
+    #[test]
+    fn test_token_budget_by_reviewer_model() {
+        let file = ConfigFile::parse(
+            r#"
+[profiles.small]
+reviewer_model = "llama3"
+
+[profiles.pinned]
+reviewer_model = "llama3"
+reviewer_token_budget = 2000
+
+[token_budgets]
+llama3 = 6000
+"#,
+        )
+        .unwrap();
+        let none = ConfigLayer::default();
+        let files = [file];
+
+        let config = RunnerConfig::from_layers(&files, Some("small"), &none, &none).unwrap();
+        assert_eq!(config.reviewer_token_budget, Some(6000));
+
+        let config = RunnerConfig::from_layers(&files, Some("pinned"), &none, &none).unwrap();
+        assert_eq!(config.reviewer_token_budget, Some(2000));
+
+        let config = RunnerConfig::from_layers(&files, None, &none, &none).unwrap();
+        assert_eq!(config.reviewer_token_budget, None);
+    }
+}
@@ -0,0 +1,321 @@
+// End-to-end tests running the full control loop against a mock OpenCode
+// server, without the real `opencode` binary.
+
+mod support;
+
+#[cfg(unix)]
+use opencode_runner::server::ServerManager;
+use opencode_runner::{
+    client::OpenCodeClient,
+    config::ControlConfig,
+    control_loop::{ControlLoop, RunResult},
+    git::{run_git, Checkpoints},
+    recording::{load_recording, replay, EventRecorder, RecordEntry},
+    reviewer::{Reviewer, ReviewerAction, ReviewerContext, ReviewerDecision, ScriptedReviewer},
+    sampler::Sampler,
+    state::State,
+};
+#[cfg(unix)]
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use support::mock_opencode::{idle, text, MockOpenCode, MOCK_SESSION_ID};
+use tokio_util::sync::CancellationToken;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decision(action: ReviewerAction) -> ReviewerDecision {
+        ReviewerDecision {
+            action,
+            reason: "scripted".to_string(),
+        }
+    }
+
+    async fn control_loop(mock: &MockOpenCode, reviewer: Arc<ScriptedReviewer>) -> ControlLoop {
+        let client = OpenCodeClient::connect(&mock.base_url()).await.unwrap();
+        let config = ControlConfig {
+            task: "Fix the failing test".to_string(),
+            max_iterations: 5,
+            inactivity_timeout: Duration::from_secs(10),
+        };
+        let reviewer: Arc<dyn Reviewer> = reviewer;
+
+        ControlLoop::new(client, reviewer, Sampler::new(100), State::new(), config)
+    }
+
+    /// Executable shell script standing in for `opencode`
+    #[cfg(unix)]
+    fn fake_binary(dir: &Path, body: &str) -> PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = dir.join("opencode");
+        std::fs::write(&path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    #[tokio::test]
+    async fn test_run_completes_against_mock_server() {
+        let mock = MockOpenCode::start(vec![vec![text("Fixed the assertion"), idle()]]).await;
+        let reviewer = Arc::new(ScriptedReviewer::new(vec![decision(
+            ReviewerAction::Complete,
+        )]));
+        let mut control = control_loop(&mock, reviewer.clone()).await;
+
+        let result = control.run(None).await.unwrap();
+
+        assert!(matches!(result, RunResult::Completed));
+        assert_eq!(mock.sessions_created(), 1);
+        assert_eq!(mock.prompts(), vec!["Fix the failing test".to_string()]);
+        assert_eq!(control.session_id(), Some("ses_mock"));
+
+        let contexts = reviewer.contexts();
+        assert_eq!(contexts.len(), 1);
+        assert!(contexts[0].worker_idle);
+        assert!(contexts[0].current_sample.contains("Fixed the assertion"));
+    }
+
+    #[tokio::test]
+    async fn test_redirect_sends_guidance_to_mock_server() {
+        let mock = MockOpenCode::start(vec![
+            vec![text("Rewriting the whole module"), idle()],
+            vec![text("Fixed just the test"), idle()],
+        ])
+        .await;
+        let reviewer = Arc::new(ScriptedReviewer::new(vec![
+            decision(ReviewerAction::Redirect {
+                guidance: "Only touch the failing test".to_string(),
+            }),
+            decision(ReviewerAction::Complete),
+        ]));
+        let mut control = control_loop(&mock, reviewer.clone()).await;
+
+        let result = control.run(None).await.unwrap();
+
+        assert!(matches!(result, RunResult::Completed));
+        assert_eq!(
+            mock.prompts(),
+            vec![
+                "Fix the failing test".to_string(),
+                "Only touch the failing test".to_string(),
+            ]
+        );
+        assert_eq!(control.state().iterations().len(), 2);
+
+        let contexts = reviewer.contexts();
+        assert!(contexts[1].current_sample.contains("Fixed just the test"));
+        assert!(!contexts[1].current_sample.contains("Rewriting"));
+    }
+
+    #[tokio::test]
+    async fn test_abort_against_mock_server() {
+        let mock = MockOpenCode::start(vec![vec![text("Trying again"), idle()]]).await;
+        let reviewer = Arc::new(ScriptedReviewer::new(vec![decision(ReviewerAction::Abort)]));
+        let mut control = control_loop(&mock, reviewer).await;
+
+        let result = control.run(None).await.unwrap();
+
+        assert!(matches!(result, RunResult::Aborted(ref reason) if reason == "scripted"));
+        assert_eq!(control.state().iterations().len(), 1);
+    }
+
+    /// Reviewer standing in for a worker that edits the tree before the
+    /// first review, then aborting
+    struct EditThenAbort {
+        repo: PathBuf,
+    }
+
+    #[async_trait::async_trait]
+    impl Reviewer for EditThenAbort {
+        fn name(&self) -> &str {
+            "edit-then-abort"
+        }
+
+        async fn review(&self, _context: &ReviewerContext) -> anyhow::Result<ReviewerDecision> {
+            std::fs::write(self.repo.join("lib.rs"), "// half-applied edit\n").unwrap();
+            std::fs::write(self.repo.join("stray.rs"), "// stray\n").unwrap();
+            Ok(decision(ReviewerAction::Abort))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_abort_before_any_continue_rolls_back_to_baseline() {
+        let repo = tempfile::tempdir().unwrap();
+        run_git(repo.path(), &["init", "--quiet"]).await.unwrap();
+        std::fs::write(repo.path().join("lib.rs"), "// original\n").unwrap();
+        run_git(repo.path(), &["add", "--all"]).await.unwrap();
+        run_git(
+            repo.path(),
+            &[
+                "-c",
+                "user.name=Test",
+                "-c",
+                "user.email=test@example.com",
+                "commit",
+                "--quiet",
+                "--message",
+                "Initial",
+            ],
+        )
+        .await
+        .unwrap();
+
+        let mock = MockOpenCode::start(vec![vec![text("Editing lib.rs"), idle()]]).await;
+        let client = OpenCodeClient::connect(&mock.base_url()).await.unwrap();
+        let config = ControlConfig {
+            task: "Fix the failing test".to_string(),
+            max_iterations: 5,
+            inactivity_timeout: Duration::from_secs(10),
+        };
+        let reviewer = Arc::new(EditThenAbort {
+            repo: repo.path().to_path_buf(),
+        });
+        let mut control =
+            ControlLoop::new(client, reviewer, Sampler::new(100), State::new(), config)
+                .with_checkpoints(Checkpoints::new(repo.path().to_path_buf(), "run-abort"));
+
+        let result = control.run(None).await.unwrap();
+
+        assert!(matches!(result, RunResult::Aborted(_)));
+        assert!(control.state().baseline_checkpoint().is_some());
+        let contents = std::fs::read_to_string(repo.path().join("lib.rs")).unwrap();
+        assert_eq!(contents, "// original\n");
+        assert!(!repo.path().join("stray.rs").exists());
+    }
+
+    #[tokio::test]
+    async fn test_cancel_aborts_mock_session() {
+        // The worker never goes idle, so only the cancellation ends the run
+        let mock = MockOpenCode::start(vec![vec![text("Still working")]]).await;
+        let reviewer = Arc::new(ScriptedReviewer::new(Vec::new()));
+        let mut control = control_loop(&mock, reviewer.clone()).await;
+
+        let cancel = control.cancellation_token();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(500)).await;
+            cancel.cancel();
+        });
+        let result = control.run(None).await.unwrap();
+
+        assert!(matches!(result, RunResult::Interrupted));
+        assert_eq!(mock.aborts(), 1);
+        assert!(reviewer.contexts().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_interrupts_mock_session() {
+        let mock = MockOpenCode::start(vec![vec![text("Still working")]]).await;
+        let reviewer = Arc::new(ScriptedReviewer::new(Vec::new()));
+        let shutdown = CancellationToken::new();
+        let mut control = control_loop(&mock, reviewer.clone())
+            .await
+            .with_shutdown(&shutdown);
+
+        // Cancelling one run leaves the shared token alone
+        control.cancellation_token().cancel();
+        assert!(!shutdown.is_cancelled());
+
+        let mut control = control_loop(&mock, reviewer).await.with_shutdown(&shutdown);
+        let signal = shutdown.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(500)).await;
+            signal.cancel();
+        });
+        let result = control.run(None).await.unwrap();
+
+        assert!(matches!(result, RunResult::Interrupted));
+        assert_eq!(mock.aborts(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_resume_prompts_idle_session() {
+        let mock = MockOpenCode::start(vec![vec![text("Picked the fix back up"), idle()]]).await;
+        let reviewer = Arc::new(ScriptedReviewer::new(vec![decision(
+            ReviewerAction::Complete,
+        )]));
+        let mut control = control_loop(&mock, reviewer)
+            .await
+            .with_session(MOCK_SESSION_ID.to_string())
+            .with_resume();
+
+        let result = control.run(None).await.unwrap();
+
+        assert!(matches!(result, RunResult::Completed));
+        assert_eq!(mock.sessions_created(), 0);
+        let prompts = mock.prompts();
+        assert_eq!(prompts.len(), 1);
+        assert!(prompts[0].contains("interrupted"));
+        assert!(prompts[0].contains("Fix the failing test"));
+    }
+
+    #[tokio::test]
+    async fn test_recorded_run_replays_offline() {
+        let mock = MockOpenCode::start(vec![vec![text("Fixed the assertion"), idle()]]).await;
+        let reviewer = Arc::new(ScriptedReviewer::new(vec![decision(
+            ReviewerAction::Complete,
+        )]));
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("events.jsonl");
+        let recorder = EventRecorder::open(&path, "Fix the failing test").unwrap();
+        let mut control = control_loop(&mock, reviewer.clone())
+            .await
+            .with_recorder(recorder);
+
+        control.run(None).await.unwrap();
+        drop(mock);
+
+        let entries = load_recording(&path).unwrap();
+        assert!(matches!(entries[0], RecordEntry::Start { .. }));
+        let events = entries
+            .iter()
+            .filter(|entry| matches!(entry, RecordEntry::Event { .. }))
+            .count();
+        assert_eq!(events, 2);
+        assert!(matches!(entries.last(), Some(RecordEntry::Review { .. })));
+
+        let offline = ScriptedReviewer::new(vec![decision(ReviewerAction::Abort)]);
+        let steps = replay(&entries, &offline, 100).await.unwrap();
+
+        assert_eq!(steps.len(), 1);
+        assert_eq!(steps[0].recorded.action, ReviewerAction::Complete);
+        assert_eq!(steps[0].replayed.action, ReviewerAction::Abort);
+        assert_eq!(
+            offline.contexts()[0].current_sample,
+            reviewer.contexts()[0].current_sample
+        );
+        assert!(offline.contexts()[0].worker_idle);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_spawn_fake_binary_uses_mock_server() {
+        let mock = MockOpenCode::start(Vec::new()).await;
+        let dir = tempfile::tempdir().unwrap();
+        let program = fake_binary(dir.path(), "exec sleep 30");
+
+        let server =
+            ServerManager::spawn_with(&program, Some(mock.port()), dir.path(), "mock", &[])
+                .await
+                .unwrap();
+
+        assert!(server.is_owned());
+        assert_eq!(server.base_url(), mock.base_url());
+        assert!(server.shutdown().await.is_ok());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_spawn_fake_binary_that_exits() {
+        let mock = MockOpenCode::start(Vec::new()).await;
+        let dir = tempfile::tempdir().unwrap();
+        let program = fake_binary(dir.path(), "exit 1");
+
+        let result =
+            ServerManager::spawn_with(&program, Some(mock.port()), dir.path(), "mock", &[]).await;
+
+        assert!(result.is_err());
+    }
+}
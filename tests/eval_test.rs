@@ -0,0 +1,265 @@
+use chrono::Utc;
+use opencode_runner::eval::{
+    evaluate, load_labels, EvalReport, Expected, Label, LatencyStats, Outcome, TranscriptResult,
+};
+use opencode_runner::recording::{RecordEntry, ReplayStep};
+use opencode_runner::reviewer::{
+    HeuristicReviewer, Reviewer, ReviewerAction, ReviewerDecision, ScriptedReviewer,
+};
+use serde_json::json;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decision(action: ReviewerAction) -> ReviewerDecision {
+        ReviewerDecision {
+            action,
+            reason: "scripted".to_string(),
+        }
+    }
+
+    fn step(iteration: usize, action: ReviewerAction, latency_ms: u64) -> ReplayStep {
+        ReplayStep {
+            iteration,
+            sample_lines: 1,
+            recorded: decision(ReviewerAction::Continue),
+            replayed: decision(action),
+            latency_ms,
+        }
+    }
+
+    fn abort_at(iteration: usize) -> Label {
+        Label {
+            expect: Expected::Abort,
+            iteration: Some(iteration),
+        }
+    }
+
+    const CONTINUE: Label = Label {
+        expect: Expected::Continue,
+        iteration: None,
+    };
+
+    /// Recording with one review per iteration
+    fn write_transcript(dir: &Path, name: &str, reviews: usize) {
+        let mut lines = vec![json!({
+            "kind": "start",
+            "at": Utc::now(),
+            "task": name,
+        })
+        .to_string()];
+        for iteration in 1..=reviews {
+            let entry = RecordEntry::Review {
+                at: Utc::now(),
+                iteration,
+                worker_idle: false,
+                verification: Vec::new(),
+                diff: None,
+                decision: decision(ReviewerAction::Continue),
+            };
+            lines.push(serde_json::to_string(&entry).unwrap());
+        }
+        std::fs::write(dir.join(format!("{}.jsonl", name)), lines.join("\n")).unwrap();
+    }
+
+    #[test]
+    fn test_load_labels() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("labels.toml"),
+            "[stuck]\nexpect = \"abort\"\niteration = 3\n\n[fine]\nexpect = \"continue\"\n",
+        )
+        .unwrap();
+
+        let labels = load_labels(dir.path()).unwrap();
+        assert_eq!(labels["stuck"], abort_at(3));
+        assert_eq!(labels["fine"], CONTINUE);
+
+        std::fs::write(
+            dir.path().join("labels.toml"),
+            "[stuck]\nexpect = \"abort\"\n",
+        )
+        .unwrap();
+        let err = load_labels(dir.path()).unwrap_err();
+        assert!(err.to_string().contains("no iteration"));
+    }
+
+    #[test]
+    fn test_score_outcomes() {
+        let on_time = [
+            step(1, ReviewerAction::Continue, 10),
+            step(2, ReviewerAction::Abort, 10),
+        ];
+        let result = TranscriptResult::score("a", abort_at(2), &on_time);
+        assert_eq!(result.outcome, Outcome::TruePositive);
+        assert_eq!(result.aborted_at, Some(2));
+        assert_eq!(result.continue_reviews, 1);
+        assert_eq!(result.false_aborts, 0);
+
+        let early = [step(1, ReviewerAction::Abort, 10)];
+        let result = TranscriptResult::score("b", abort_at(2), &early);
+        assert_eq!(result.outcome, Outcome::FalsePositive);
+        assert_eq!(result.false_aborts, 1);
+
+        let missed = [
+            step(1, ReviewerAction::Continue, 10),
+            step(
+                2,
+                ReviewerAction::Redirect {
+                    guidance: "Try again".to_string(),
+                },
+                10,
+            ),
+        ];
+        let result = TranscriptResult::score("c", abort_at(2), &missed);
+        assert_eq!(result.outcome, Outcome::FalseNegative);
+
+        let result = TranscriptResult::score("d", CONTINUE, &missed);
+        assert_eq!(result.outcome, Outcome::TrueNegative);
+        assert_eq!(result.continue_reviews, 2);
+    }
+
+    #[test]
+    fn test_report_metrics() {
+        let results = vec![
+            TranscriptResult::score(
+                "caught",
+                abort_at(2),
+                &[
+                    step(1, ReviewerAction::Continue, 100),
+                    step(2, ReviewerAction::Abort, 200),
+                ],
+            ),
+            TranscriptResult::score(
+                "missed",
+                abort_at(1),
+                &[step(1, ReviewerAction::Continue, 300)],
+            ),
+            TranscriptResult::score(
+                "false_alarm",
+                CONTINUE,
+                &[
+                    step(1, ReviewerAction::Continue, 400),
+                    step(2, ReviewerAction::Abort, 500),
+                ],
+            ),
+        ];
+
+        let report = EvalReport::new(results);
+
+        assert_eq!(report.precision, Some(0.5));
+        assert_eq!(report.recall, Some(0.5));
+        // Three reviews should have continued, one of them aborted
+        assert_eq!(report.false_abort_rate, Some(1.0 / 3.0));
+        assert_eq!(report.latency.mean_ms, 300);
+        assert_eq!(report.latency.max_ms, 500);
+        assert!(report.format_summary().contains("Precision: 50.0%"));
+    }
+
+    #[test]
+    fn test_report_without_aborts() {
+        let report = EvalReport::new(vec![TranscriptResult::score(
+            "fine",
+            CONTINUE,
+            &[step(1, ReviewerAction::Continue, 10)],
+        )]);
+
+        assert_eq!(report.precision, None);
+        assert_eq!(report.recall, None);
+        assert_eq!(report.false_abort_rate, Some(0.0));
+        assert!(report.format_summary().contains("Precision: n/a"));
+    }
+
+    #[test]
+    fn test_latency_percentiles() {
+        let samples: Vec<u64> = (1..=20).collect();
+        let stats = LatencyStats::from_samples(&samples);
+
+        assert_eq!(stats.p50_ms, 10);
+        assert_eq!(stats.p95_ms, 19);
+        assert_eq!(stats.max_ms, 20);
+        assert_eq!(LatencyStats::from_samples(&[]), LatencyStats::default());
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("labels.toml"),
+            "[fine]\nexpect = \"continue\"\n\n[stuck]\nexpect = \"abort\"\niteration = 2\n",
+        )
+        .unwrap();
+        write_transcript(dir.path(), "fine", 2);
+        write_transcript(dir.path(), "stuck", 3);
+        write_transcript(dir.path(), "unlabelled", 1);
+
+        // Transcripts run in name order: fine, then stuck
+        let scripts = Mutex::new(vec![
+            vec![
+                decision(ReviewerAction::Continue),
+                decision(ReviewerAction::Continue),
+            ],
+            vec![
+                decision(ReviewerAction::Continue),
+                decision(ReviewerAction::Abort),
+                decision(ReviewerAction::Continue),
+            ],
+        ]);
+        let reviewers = Mutex::new(Vec::new());
+        let make_reviewer = || {
+            let reviewer = Arc::new(ScriptedReviewer::new(scripts.lock().unwrap().remove(0)));
+            reviewers.lock().unwrap().push(reviewer.clone());
+            Ok(reviewer as Arc<dyn Reviewer>)
+        };
+
+        let report = evaluate(dir.path(), make_reviewer, 100).await.unwrap();
+
+        assert_eq!(report.transcripts.len(), 2);
+        assert_eq!(report.transcripts[0].outcome, Outcome::TrueNegative);
+        assert_eq!(report.transcripts[1].outcome, Outcome::TruePositive);
+        // Replay stops at the abort instead of reviewing iteration 3
+        assert_eq!(report.transcripts[1].reviews, 2);
+        let reviewers = reviewers.lock().unwrap();
+        assert_eq!(reviewers.len(), 2);
+        assert_eq!(reviewers[0].remaining(), 0);
+        assert_eq!(reviewers[1].remaining(), 1);
+        assert_eq!(report.precision, Some(1.0));
+        assert_eq!(report.recall, Some(1.0));
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_scores_transcript_independently_of_order() {
+        let heuristic = || Ok(Arc::new(HeuristicReviewer::new()) as Arc<dyn Reviewer>);
+
+        let alone = tempfile::tempdir().unwrap();
+        std::fs::write(
+            alone.path().join("labels.toml"),
+            "[quiet]\nexpect = \"continue\"\n",
+        )
+        .unwrap();
+        write_transcript(alone.path(), "quiet", 1);
+
+        // The heuristic reviewer aborts after two reviews without new output,
+        // so history shared with "earlier" would make it abort "quiet"
+        let after = tempfile::tempdir().unwrap();
+        std::fs::write(
+            after.path().join("labels.toml"),
+            "[earlier]\nexpect = \"continue\"\n\n[quiet]\nexpect = \"continue\"\n",
+        )
+        .unwrap();
+        write_transcript(after.path(), "earlier", 1);
+        write_transcript(after.path(), "quiet", 1);
+
+        let alone = evaluate(alone.path(), heuristic, 100).await.unwrap();
+        let after = evaluate(after.path(), heuristic, 100).await.unwrap();
+
+        let quiet = &after.transcripts[1];
+        assert_eq!(quiet.name, "quiet");
+        assert_eq!(quiet.outcome, Outcome::TrueNegative);
+        assert_eq!(quiet.outcome, alone.transcripts[0].outcome);
+        assert_eq!(quiet.aborted_at, alone.transcripts[0].aborted_at);
+    }
+}
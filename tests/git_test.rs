@@ -0,0 +1,228 @@
+use opencode_runner::git::{repo_root, run_git, Checkpoints, DiffTracker, Worktree};
+use opencode_runner::run_store::RunStore;
+use std::path::Path;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Create a repository with one commit and a committer identity
+    async fn init_repo(dir: &Path) {
+        run_git(dir, &["init", "--quiet"]).await.unwrap();
+        run_git(dir, &["config", "user.name", "Test"])
+            .await
+            .unwrap();
+        run_git(dir, &["config", "user.email", "test@example.com"])
+            .await
+            .unwrap();
+        std::fs::create_dir(dir.join("src")).unwrap();
+        std::fs::write(dir.join("src/lib.rs"), "// lib\n").unwrap();
+        run_git(dir, &["add", "--all"]).await.unwrap();
+        run_git(dir, &["commit", "--quiet", "--message", "Initial"])
+            .await
+            .unwrap();
+    }
+
+    async fn branch_exists(repo: &Path, branch: &str) -> bool {
+        run_git(repo, &["rev-parse", "--verify", "--quiet", branch])
+            .await
+            .is_ok()
+    }
+
+    #[tokio::test]
+    async fn test_run_git_reports_failures() {
+        let dir = tempfile::tempdir().unwrap();
+        let err = run_git(dir.path(), &["rev-parse", "--show-toplevel"])
+            .await
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("git rev-parse --show-toplevel failed"));
+        assert!(repo_root(dir.path()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_worktree_keep_commits_to_branch() {
+        let repo = tempfile::tempdir().unwrap();
+        let worktrees = tempfile::tempdir().unwrap();
+        init_repo(repo.path()).await;
+
+        let worktree = Worktree::create(repo.path(), worktrees.path(), "run-1")
+            .await
+            .unwrap();
+        assert_eq!(worktree.branch, "opencode_runner/run-1");
+        assert!(worktree.path.join("src/lib.rs").exists());
+        assert_eq!(
+            worktree.map_dir(&repo.path().join("src")),
+            worktree.path.join("src")
+        );
+
+        std::fs::write(worktree.path.join("src/new.rs"), "// new\n").unwrap();
+        let path = worktree.path.clone();
+        let branch = worktree.branch.clone();
+        worktree.keep("opencode_runner: add new.rs").await.unwrap();
+
+        assert!(!path.exists());
+        assert!(branch_exists(repo.path(), &branch).await);
+        let files = run_git(
+            repo.path(),
+            &["show", "--name-only", "--format=%s", &branch],
+        )
+        .await
+        .unwrap();
+        assert!(files.contains("opencode_runner: add new.rs"));
+        assert!(files.contains("src/new.rs"));
+        // The main checkout is untouched
+        assert!(!repo.path().join("src/new.rs").exists());
+    }
+
+    #[tokio::test]
+    async fn test_worktree_discard_removes_branch() {
+        let repo = tempfile::tempdir().unwrap();
+        let worktrees = tempfile::tempdir().unwrap();
+        init_repo(repo.path()).await;
+
+        let worktree = Worktree::create(repo.path(), worktrees.path(), "run-2")
+            .await
+            .unwrap();
+        std::fs::write(worktree.path.join("src/lib.rs"), "// broken\n").unwrap();
+        let path = worktree.path.clone();
+        worktree.discard().await.unwrap();
+
+        assert!(!path.exists());
+        assert!(!branch_exists(repo.path(), "opencode_runner/run-2").await);
+        let contents = std::fs::read_to_string(repo.path().join("src/lib.rs")).unwrap();
+        assert_eq!(contents, "// lib\n");
+    }
+
+    #[tokio::test]
+    async fn test_commit_all_without_changes() {
+        let repo = tempfile::tempdir().unwrap();
+        let worktrees = tempfile::tempdir().unwrap();
+        init_repo(repo.path()).await;
+
+        let worktree = Worktree::create(repo.path(), worktrees.path(), "run-3")
+            .await
+            .unwrap();
+        assert!(!worktree.commit_all("nothing").await.unwrap());
+        worktree.discard().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_checkpoint_restore() {
+        let repo = tempfile::tempdir().unwrap();
+        init_repo(repo.path()).await;
+
+        let checkpoints = Checkpoints::new(repo.path().join("src"), "run-4");
+        std::fs::write(repo.path().join("src/lib.rs"), "// good\n").unwrap();
+        std::fs::write(repo.path().join("src/kept.rs"), "// kept\n").unwrap();
+        let good = checkpoints.create("iteration 1").await.unwrap();
+        assert!(branch_exists(repo.path(), checkpoints.ref_name()).await);
+
+        std::fs::write(repo.path().join("src/lib.rs"), "// broken\n").unwrap();
+        std::fs::write(repo.path().join("src/stray.rs"), "// stray\n").unwrap();
+        std::fs::remove_file(repo.path().join("src/kept.rs")).unwrap();
+        let bad = checkpoints.create("iteration 2").await.unwrap();
+        let parent = run_git(repo.path(), &["rev-parse", &format!("{}^", bad)])
+            .await
+            .unwrap();
+        assert_eq!(parent, good);
+
+        checkpoints.restore(&good).await.unwrap();
+        let contents = std::fs::read_to_string(repo.path().join("src/lib.rs")).unwrap();
+        assert_eq!(contents, "// good\n");
+        assert!(repo.path().join("src/kept.rs").exists());
+        assert!(!repo.path().join("src/stray.rs").exists());
+        // Checkpoints never touch the branch or the index
+        let status = run_git(repo.path(), &["diff", "--cached", "--name-only"])
+            .await
+            .unwrap();
+        assert!(status.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_checkpoint_restore_leaves_run_state_alone() {
+        let repo = tempfile::tempdir().unwrap();
+        init_repo(repo.path()).await;
+        let store = RunStore::create(&repo.path().join(".opencode_runner/runs"), "run-5").unwrap();
+        let checkpoints = Checkpoints::new(repo.path().to_path_buf(), "run-5");
+
+        std::fs::write(store.dir().join("run.json"), "iteration 1").unwrap();
+        let good = checkpoints.create("iteration 1").await.unwrap();
+        let files = run_git(repo.path(), &["ls-tree", "-r", "--name-only", &good])
+            .await
+            .unwrap();
+        assert!(!files.contains(".opencode_runner"));
+
+        std::fs::write(store.dir().join("run.json"), "iteration 2").unwrap();
+        std::fs::write(store.dir().join("events.jsonl"), "{}\n").unwrap();
+        std::fs::write(repo.path().join("src/stray.rs"), "// stray\n").unwrap();
+        checkpoints.restore(&good).await.unwrap();
+
+        assert!(!repo.path().join("src/stray.rs").exists());
+        let record = std::fs::read_to_string(store.dir().join("run.json")).unwrap();
+        assert_eq!(record, "iteration 2");
+        assert!(store.dir().join("events.jsonl").exists());
+    }
+
+    #[tokio::test]
+    async fn test_diff_tracker_reports_changes_between_reviews() {
+        let repo = tempfile::tempdir().unwrap();
+        init_repo(repo.path()).await;
+        // Changes made before the run are not the worker's
+        std::fs::write(repo.path().join("src/lib.rs"), "// dirty\n").unwrap();
+
+        let mut tracker = DiffTracker::new(repo.path().to_path_buf(), "run-5");
+        tracker.reset().await;
+        std::fs::write(repo.path().join("src/new.rs"), "fn added() {}\n").unwrap();
+
+        let diff = tracker.diff().await.unwrap();
+        assert!(diff.stat.contains("src/new.rs"));
+        assert!(!diff.stat.contains("src/lib.rs"));
+        assert!(diff.patch.contains("+fn added() {}"));
+        assert!(!diff.truncated);
+
+        let diff = tracker.diff().await.unwrap();
+        assert!(diff.is_empty());
+        assert_eq!(diff.unchanged_reviews, 1);
+        assert_eq!(tracker.diff().await.unwrap().unchanged_reviews, 2);
+
+        std::fs::write(repo.path().join("src/big.rs"), "// line\n".repeat(2000)).unwrap();
+        let diff = tracker.diff().await.unwrap();
+        assert!(diff.truncated);
+        assert_eq!(diff.unchanged_reviews, 0);
+
+        // Nothing was staged in the real index
+        let staged = run_git(repo.path(), &["diff", "--cached", "--name-only"])
+            .await
+            .unwrap();
+        assert!(staged.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_diff_tracker_ignores_run_state() {
+        let repo = tempfile::tempdir().unwrap();
+        init_repo(repo.path()).await;
+        let store = RunStore::create(&repo.path().join(".opencode_runner/runs"), "run-7").unwrap();
+
+        let mut tracker = DiffTracker::new(repo.path().to_path_buf(), "run-7");
+        tracker.reset().await;
+        std::fs::write(store.dir().join("run.json"), "iteration 1").unwrap();
+        std::fs::write(store.dir().join("events.jsonl"), "{}\n").unwrap();
+
+        let diff = tracker.diff().await.unwrap();
+        assert!(diff.is_empty());
+        assert_eq!(diff.unchanged_reviews, 1);
+
+        std::fs::write(store.dir().join("run.json"), "iteration 2").unwrap();
+        assert_eq!(tracker.diff().await.unwrap().unchanged_reviews, 2);
+    }
+
+    #[tokio::test]
+    async fn test_diff_tracker_outside_repository() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut tracker = DiffTracker::new(dir.path().to_path_buf(), "run-6");
+        tracker.reset().await;
+        assert!(tracker.diff().await.is_none());
+    }
+}
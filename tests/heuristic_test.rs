@@ -0,0 +1,104 @@
+use opencode_runner::reviewer::{HeuristicReviewer, ReviewerAction};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn numbered_lines(prefix: &str, count: usize) -> String {
+        (1..=count)
+            .map(|i| format!("{} {}", prefix, i))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn test_repeated_line_ratio() {
+        assert_eq!(HeuristicReviewer::repeated_line_ratio(""), 0.0);
+        assert_eq!(HeuristicReviewer::repeated_line_ratio("a\nb\nc\nd"), 0.0);
+        assert_eq!(HeuristicReviewer::repeated_line_ratio("a\na\na\na"), 0.75);
+    }
+
+    #[test]
+    fn test_tool_sequence_extraction() {
+        let sample = "Reading the file\n[Tool: read_file]\nNow editing\n[Tool: edit_file]";
+        assert_eq!(
+            HeuristicReviewer::tool_sequence(sample),
+            vec!["[Tool: read_file]", "[Tool: edit_file]"]
+        );
+    }
+
+    #[test]
+    fn test_progressing_output_continues() {
+        let reviewer = HeuristicReviewer::new();
+
+        for i in 0..5 {
+            let sample = numbered_lines(&format!("Iteration {} step", i), 12);
+            let decision = reviewer.assess(&sample);
+            assert_eq!(decision.action, ReviewerAction::Continue);
+        }
+    }
+
+    #[test]
+    fn test_repeated_lines_abort() {
+        let reviewer = HeuristicReviewer::new();
+        let sample = vec!["Let me try that again"; 15].join("\n");
+
+        let decision = reviewer.assess(&sample);
+        assert_eq!(decision.action, ReviewerAction::Abort);
+        assert!(decision.reason.contains("repeats"));
+    }
+
+    #[test]
+    fn test_identical_tool_sequences_abort() {
+        let reviewer = HeuristicReviewer::new();
+
+        for i in 0..2 {
+            let sample = format!("Attempt {}\n[Tool: cargo build]\n[Tool: read_file]", i);
+            assert_eq!(reviewer.assess(&sample).action, ReviewerAction::Continue);
+        }
+
+        let decision = reviewer.assess("Attempt 2\n[Tool: cargo build]\n[Tool: read_file]");
+        assert_eq!(decision.action, ReviewerAction::Abort);
+        assert!(decision
+            .reason
+            .contains("tool calls repeated for 3 iterations"));
+    }
+
+    #[test]
+    fn test_tool_only_iterations_on_different_files_continue() {
+        let reviewer = HeuristicReviewer::new();
+
+        for file in ["src/lib.rs", "src/cli.rs", "src/config.rs", "README.md"] {
+            let sample = format!("[Tool: read {}]\n[Tool: edit {}]", file, file);
+            let decision = reviewer.assess(&sample);
+            assert_eq!(decision.action, ReviewerAction::Continue, "{}", file);
+        }
+    }
+
+    #[test]
+    fn test_repeated_errors_abort() {
+        let reviewer = HeuristicReviewer::new();
+
+        for i in 0..2 {
+            let sample = format!("Fix attempt {}\nerror: cannot find value `x`", i);
+            assert_eq!(reviewer.assess(&sample).action, ReviewerAction::Continue);
+        }
+
+        let decision = reviewer.assess("Fix attempt 2\nerror: cannot find value `x`");
+        assert_eq!(decision.action, ReviewerAction::Abort);
+        assert!(decision.reason.contains("Error repeated 3 times"));
+    }
+
+    #[test]
+    fn test_no_new_output_aborts() {
+        let reviewer = HeuristicReviewer::new();
+        let sample = "Thinking about the problem\nStill thinking";
+
+        assert_eq!(reviewer.assess(sample).action, ReviewerAction::Continue);
+        assert_eq!(reviewer.assess(sample).action, ReviewerAction::Continue);
+
+        let decision = reviewer.assess(sample);
+        assert_eq!(decision.action, ReviewerAction::Abort);
+        assert!(decision.reason.contains("No new output"));
+    }
+}
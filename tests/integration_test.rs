@@ -3,15 +3,19 @@
 // These tests simulate realistic usage scenarios for the OpenCode Runner system,
 // mocking dependencies where appropriate.
 
+mod support;
+
 use opencode_runner::{
     config::ControlConfig,
-    environment::{get_env_with_default, get_env_with_default_bool, get_env_with_default_int},
-    reviewer::{ReviewerAction, ReviewerClient, ReviewerContext, ReviewerDecision},
+    control_loop::RunResult,
+    environment::load_config_from_env,
+    reviewer::{Reviewer, ReviewerAction, ReviewerClient, ReviewerContext, ReviewerDecision},
     sampler::Sampler,
     state::State,
 };
 use opencode_rs::types::event::Event;
 use opencode_rs::types::message::Part;
+use support::mock_openai::{MockOpenAi, Reply};
 
 #[cfg(test)]
 mod integration_tests {
@@ -123,6 +127,9 @@ mod integration_tests {
                 "Iteration 2: Still working".to_string(),
             ],
             current_sample: "Code output...".to_string(),
+            worker_idle: false,
+            verification: Vec::new(),
+            diff: None,
         };
 
         let client = ReviewerClient::new(
@@ -142,24 +149,29 @@ mod integration_tests {
 
     #[tokio::test]
     async fn test_reviewer_retry_logic() {
-        // Test that ReviewerClient implements exponential backoff retry logic
-        let client = ReviewerClient::new(
-            "http://localhost:11434/v1".to_string(),
-            "llama3.1".to_string(),
-        );
+        // Every attempt fails, so the client falls back to Continue
+        let mock = MockOpenAi::start(vec![
+            Reply::Status(500),
+            Reply::Status(500),
+            Reply::Status(500),
+        ])
+        .await;
+        let mut client = ReviewerClient::new(mock.base_url(), "llama3.1".to_string());
+        client.retry_base_delay = std::time::Duration::from_millis(1);
 
         let context = ReviewerContext {
             task_description: "Test task".to_string(),
             iteration: 1,
             previous_summaries: vec![],
             current_sample: "Test output".to_string(),
+            worker_idle: false,
+            verification: Vec::new(),
+            diff: None,
         };
 
-        // Test that review_with_retry returns Result<ReviewerDecision>
-        // The actual API call would fail in test environment, so we verify the structure
-        let result = client.review_with_retry(&context).await;
-        // In test environment, this will likely fail due to no server, but that's expected
-        assert!(result.is_err() || result.is_ok());
+        let decision = client.review_with_retry(&context).await.unwrap().decision;
+        assert_eq!(decision.action, ReviewerAction::Continue);
+        assert_eq!(mock.request_count(), 3);
     }
 
     // ============ Test State Management Integration ============
@@ -259,83 +271,30 @@ mod integration_tests {
     // ============ Test Error Handling Integration ============
 
     #[test]
-    fn test_environment_variables() {
-        // Test that environment variables can be loaded
-        // Note: Tests run in parallel, so we set env vars fresh for this test
+    fn test_environment_layer() {
+        // Both cases in one test, since tests share the process environment
         std::env::set_var("OPCODE_TASK", "Test task");
         std::env::set_var("OPCODE_MAX_ITERATIONS", "15");
-        std::env::set_var("OPCODE_INACTIVITY_TIMEOUT", "45");
+        std::env::set_var("OPCODE_HEADLESS", "yes");
 
-        let config = ControlConfig::from_env().unwrap();
+        let env = load_config_from_env().unwrap();
+        assert_eq!(env.task.as_deref(), Some("Test task"));
+        assert_eq!(env.max_iterations, Some(15));
+        assert_eq!(env.headless, Some(true));
 
-        // Due to parallel test execution, env vars might be cleared by other tests
-        // So we verify the structure works rather than exact values
-        assert!(!config.task.is_empty() || config.task.is_empty()); // Task is read (may be empty due to race)
-        assert_eq!(config.max_iterations, 15); // This should still work
-        // Note: ControlConfig doesn't have headless field, remove that check
+        std::env::set_var("OPCODE_MAX_ITERATIONS", "lots");
+        let err = load_config_from_env().unwrap_err();
+        assert!(err.to_string().contains("OPCODE_MAX_ITERATIONS"));
 
-        // Clean up
         std::env::remove_var("OPCODE_TASK");
         std::env::remove_var("OPCODE_MAX_ITERATIONS");
-        std::env::remove_var("OPCODE_INACTIVITY_TIMEOUT");
-    }
-
-    #[test]
-    fn test_environment_variables_with_defaults() {
-        // Test that environment variables can use defaults when not set
-        // Remove all environment variables to test defaults
-        let _ = std::env::remove_var("OPCODE_TASK");
-        let _ = std::env::remove_var("OPCODE_MAX_ITERATIONS");
-        let _ = std::env::remove_var("OPCODE_INACTIVITY_TIMEOUT");
-
-        let config = ControlConfig::from_env().unwrap();
-
-        // Defaults should be used
-        assert_eq!(config.task, "");
-        assert_eq!(config.max_iterations, 10);
-        assert_eq!(
-            config.inactivity_timeout,
-            std::time::Duration::from_secs(30)
-        );
-    }
-
-    #[test]
-    fn test_get_env_with_default() {
-        // Test get_env_with_default function
-        assert_eq!(
-            get_env_with_default("TEST_KEY", "default_value"),
-            "default_value"
-        );
+        std::env::remove_var("OPCODE_HEADLESS");
 
-        std::env::set_var("TEST_KEY", "actual_value");
-        assert_eq!(
-            get_env_with_default("TEST_KEY", "default_value"),
-            "actual_value"
-        );
-
-        std::env::remove_var("TEST_KEY");
-    }
-
-    #[test]
-    fn test_get_env_with_default_int() {
-        // Test get_env_with_default_int function
-        assert_eq!(get_env_with_default_int("TEST_KEY", 42), 42);
-
-        std::env::set_var("TEST_KEY", "100");
-        assert_eq!(get_env_with_default_int("TEST_KEY", 42), 100);
-
-        std::env::remove_var("TEST_KEY");
-    }
-
-    #[test]
-    fn test_get_env_with_default_bool() {
-        // Test get_env_with_default_bool function
-        assert_eq!(get_env_with_default_bool("TEST_KEY", true), true);
-
-        std::env::set_var("TEST_KEY", "false");
-        assert_eq!(get_env_with_default_bool("TEST_KEY", true), false);
-
-        std::env::remove_var("TEST_KEY");
+        // Unset variables leave the defaults to the layers below
+        let env = load_config_from_env().unwrap();
+        assert_eq!(env.task, None);
+        assert_eq!(env.max_iterations, None);
+        assert_eq!(env.headless, None);
     }
 
     // ============ Test Integration Scenarios ============
@@ -380,6 +339,9 @@ mod integration_tests {
             iteration: state.current_iteration(),
             previous_summaries: state.get_previous_summaries(2),
             current_sample: sample.clone(),
+            worker_idle: false,
+            verification: Vec::new(),
+            diff: None,
         };
 
         // 6. Create reviewer client and get decision
@@ -393,10 +355,9 @@ mod integration_tests {
         assert!(decision_result.is_err() || decision_result.is_ok());
 
         // If we got a decision, record it
-        if let Ok(decision) = decision_result {
+        if let Ok(outcome) = decision_result {
             let sample_size = 50;
-            let retry_count = 0;
-            state.record_decision(sample_size, decision, retry_count);
+            state.record_decision(sample_size, outcome.decision, outcome.retries);
 
             // 8. Verify state
             let previous_summaries = state.get_previous_summaries(5);
@@ -447,6 +408,9 @@ mod integration_tests {
             iteration: 4,
             previous_summaries: summaries.clone(),
             current_sample: "Final code output...".to_string(),
+            worker_idle: false,
+            verification: Vec::new(),
+            diff: None,
         };
 
         let previous_formatted = context.previous_summaries.clone();
@@ -525,4 +489,15 @@ mod integration_tests {
 
         // Should not crash with more lines than max
     }
+
+    #[test]
+    fn test_run_result_display() {
+        assert_eq!(RunResult::Completed.to_string(), "Completed");
+        assert_eq!(
+            RunResult::Aborted("Stuck in loop".to_string()).to_string(),
+            "Aborted: Stuck in loop"
+        );
+        assert_eq!(RunResult::MaxIterations.to_string(), "Max iterations reached");
+        assert_eq!(RunResult::Interrupted.to_string(), "Interrupted by user");
+    }
 }
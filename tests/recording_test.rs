@@ -0,0 +1,177 @@
+use chrono::Utc;
+use opencode_rs::types::event::{Event, MessagePartEventProps};
+use opencode_rs::types::message::Part;
+use opencode_runner::recording::{
+    load_recording, replay, EventRecorder, RecordEntry, ReplayReport,
+};
+use opencode_runner::reviewer::{
+    ReviewerAction, ReviewerContext, ReviewerDecision, ScriptedReviewer,
+};
+use serde_json::json;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decision(action: ReviewerAction, reason: &str) -> ReviewerDecision {
+        ReviewerDecision {
+            action,
+            reason: reason.to_string(),
+        }
+    }
+
+    fn text_event(text: &str) -> RecordEntry {
+        RecordEntry::Event {
+            at: Utc::now(),
+            event: json!({
+                "type": "message.part.updated",
+                "properties": { "part": { "type": "text", "text": text } }
+            }),
+        }
+    }
+
+    fn review(iteration: usize, decision: ReviewerDecision) -> RecordEntry {
+        RecordEntry::Review {
+            at: Utc::now(),
+            iteration,
+            worker_idle: false,
+            verification: Vec::new(),
+            diff: None,
+            decision,
+        }
+    }
+
+    #[test]
+    fn test_recorder_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("events.jsonl");
+
+        let mut recorder = EventRecorder::open(&path, "Fix the build").unwrap();
+        recorder
+            .record_event(&Event::MessagePartUpdated {
+                properties: Box::new(MessagePartEventProps {
+                    session_id: None,
+                    message_id: None,
+                    index: None,
+                    part: Some(Part::Text {
+                        id: None,
+                        text: "Running cargo build".to_string(),
+                        synthetic: None,
+                        ignored: None,
+                        metadata: None,
+                    }),
+                    delta: None,
+                    extra: serde_json::Value::Null,
+                }),
+            })
+            .unwrap();
+        recorder
+            .record_history(&["Earlier output".to_string()])
+            .unwrap();
+        let context = ReviewerContext {
+            iteration: 1,
+            worker_idle: true,
+            ..Default::default()
+        };
+        recorder
+            .record_review(&context, &decision(ReviewerAction::Complete, "Done"))
+            .unwrap();
+        drop(recorder);
+
+        let entries = load_recording(&path).unwrap();
+        assert_eq!(entries.len(), 4);
+        assert!(
+            matches!(entries[0], RecordEntry::Start { ref task, .. } if task == "Fix the build")
+        );
+        assert!(matches!(entries[1], RecordEntry::Event { .. }));
+        assert!(matches!(entries[2], RecordEntry::History { ref lines, .. } if lines.len() == 1));
+        match entries[3] {
+            RecordEntry::Review {
+                iteration,
+                worker_idle,
+                ref decision,
+                ..
+            } => {
+                assert_eq!(iteration, 1);
+                assert!(worker_idle);
+                assert_eq!(decision.action, ReviewerAction::Complete);
+            }
+            _ => panic!("Expected Review"),
+        }
+    }
+
+    #[test]
+    fn test_reopening_appends() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("events.jsonl");
+
+        drop(EventRecorder::open(&path, "Task").unwrap());
+        drop(EventRecorder::open(&path, "Task").unwrap());
+
+        let entries = load_recording(&path).unwrap();
+        assert_eq!(entries.len(), 2);
+    }
+
+    #[test]
+    fn test_load_reports_bad_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("events.jsonl");
+        std::fs::write(
+            &path,
+            "{\"kind\":\"start\",\"at\":\"2026-01-01T00:00:00Z\",\"task\":\"Task\"}\nnot json\n",
+        )
+        .unwrap();
+
+        let err = load_recording(&path).unwrap_err();
+        assert!(err.to_string().contains("line 2"));
+    }
+
+    #[tokio::test]
+    async fn test_replay_reviews_recorded_samples() {
+        let entries = vec![
+            RecordEntry::Start {
+                at: Utc::now(),
+                task: "Fix the build".to_string(),
+            },
+            text_event("Editing main.rs"),
+            review(1, decision(ReviewerAction::Continue, "Progressing")),
+            text_event("Editing main.rs again"),
+            RecordEntry::History {
+                at: Utc::now(),
+                lines: vec![
+                    "Editing main.rs again".to_string(),
+                    "Missed line".to_string(),
+                ],
+            },
+            review(3, decision(ReviewerAction::Abort, "Looping")),
+        ];
+        let reviewer = ScriptedReviewer::new(vec![
+            decision(ReviewerAction::Continue, "Fine"),
+            decision(ReviewerAction::Continue, "Still fine"),
+        ]);
+
+        let steps = replay(&entries, &reviewer, 100).await.unwrap();
+
+        assert_eq!(steps.len(), 2);
+        assert!(steps[0].matches());
+        assert!(!steps[1].matches());
+        assert_eq!(steps[1].recorded.reason, "Looping");
+        assert_eq!(steps[1].sample_lines, 2);
+
+        let contexts = reviewer.contexts();
+        assert_eq!(contexts[0].task_description, "Fix the build");
+        assert_eq!(contexts[0].current_sample, "Editing main.rs");
+        // The sampler is cleared after each review and backfill skips seen lines
+        assert_eq!(
+            contexts[1].current_sample,
+            "Editing main.rs again\nMissed line"
+        );
+        assert_eq!(contexts[1].iteration, 3);
+        assert_eq!(contexts[1].previous_summaries.len(), 1);
+        assert!(contexts[1].previous_summaries[0].contains("Progressing"));
+
+        let report = ReplayReport::new(steps);
+        assert_eq!(report.matched, 1);
+        assert_eq!(report.format_summary(), "1 of 2 decisions unchanged");
+    }
+}
@@ -0,0 +1,228 @@
+use opencode_runner::report::{junit_xml, result_label, RunReport, RunSettings};
+use opencode_runner::{
+    ControlConfig, ReviewerAction, ReviewerBackend, ReviewerDecision, RunResult, State, TokenUsage,
+    VerificationResult,
+};
+use std::path::PathBuf;
+use std::time::Duration;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_state() -> State {
+        let mut state = State::new();
+        state.start_iteration();
+        state.record_decision(
+            40,
+            ReviewerDecision {
+                action: ReviewerAction::Continue,
+                reason: "Making progress".to_string(),
+            },
+            1,
+        );
+        state.start_iteration();
+        state.record_decision(
+            12,
+            ReviewerDecision {
+                action: ReviewerAction::Abort,
+                reason: "Stuck in a loop".to_string(),
+            },
+            0,
+        );
+        state.record_message_usage(
+            "msg_1".to_string(),
+            TokenUsage {
+                input: 1000,
+                output: 200,
+                cost: 0.05,
+                ..Default::default()
+            },
+        );
+        state
+    }
+
+    fn test_config() -> ControlConfig {
+        ControlConfig::new("Fix the build".to_string(), 10, Duration::from_secs(30))
+    }
+
+    #[test]
+    fn test_report_from_state() {
+        let state = test_state();
+        let result = RunResult::Aborted("Stuck in a loop".to_string());
+
+        let report = RunReport::new(&test_config(), Some("ses_123"), &state, &result)
+            .with_run_id(Some("20250101-000000-1"));
+
+        assert_eq!(report.task, "Fix the build");
+        assert_eq!(report.session_id.as_deref(), Some("ses_123"));
+        assert_eq!(report.run_id.as_deref(), Some("20250101-000000-1"));
+        assert_eq!(report.result, "aborted");
+        assert_eq!(report.reason.as_deref(), Some("Stuck in a loop"));
+        assert_eq!(report.iterations.len(), 2);
+        assert_eq!(report.usage.output, 200);
+        assert!(!report.passed());
+    }
+
+    #[test]
+    fn test_report_json_round_trip() {
+        let state = test_state();
+        let report = RunReport::new(&test_config(), None, &state, &RunResult::Completed);
+
+        let json = report.to_json().unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["result"], "completed");
+        assert_eq!(value["iterations"][1]["decision"]["action"], "abort");
+        assert_eq!(value["iterations"][0]["reviewer_retry_count"], 1);
+        assert_eq!(value["usage"]["input"], 1000);
+
+        let parsed: RunReport = serde_json::from_str(&json).unwrap();
+        assert!(parsed.passed());
+    }
+
+    #[test]
+    fn test_report_includes_settings() {
+        let settings = RunSettings {
+            working_dir: PathBuf::from("/work"),
+            worker_model: "worker-model".to_string(),
+            reviewer_backend: ReviewerBackend::Anthropic,
+            reviewer_model: "reviewer-model".to_string(),
+            reviewer_url: "https://api.example.com".to_string(),
+            sample_lines: 50,
+        };
+        let report = RunReport::new(&test_config(), None, &test_state(), &RunResult::Completed)
+            .with_settings(Some(settings));
+
+        let value: serde_json::Value = serde_json::from_str(&report.to_json().unwrap()).unwrap();
+        assert_eq!(value["settings"]["worker_model"], "worker-model");
+        assert_eq!(value["settings"]["reviewer_backend"], "anthropic");
+        assert_eq!(value["settings"]["sample_lines"], 50);
+
+        let markdown = report.to_markdown();
+        assert!(markdown.contains("| Worker | `worker-model` |"));
+        assert!(markdown.contains("| Reviewer | anthropic `reviewer-model` |"));
+    }
+
+    #[test]
+    fn test_write_json_creates_parent_dirs() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("reports/run.json");
+        let report = RunReport::new(
+            &test_config(),
+            None,
+            &State::new(),
+            &RunResult::MaxIterations,
+        );
+
+        report.write_json(&path).unwrap();
+
+        let written = std::fs::read_to_string(&path).unwrap();
+        assert!(written.contains("\"max_iterations\""));
+    }
+
+    #[test]
+    fn test_result_labels() {
+        assert_eq!(result_label(&RunResult::Completed), "completed");
+        assert_eq!(result_label(&RunResult::Interrupted), "interrupted");
+    }
+
+    #[test]
+    fn test_markdown_has_iteration_table() {
+        let state = test_state();
+        let result = RunResult::Aborted("Stuck in a loop".to_string());
+        let report = RunReport::new(&test_config(), Some("ses_123"), &state, &result);
+
+        let markdown = report.to_markdown();
+        assert!(markdown.contains("## OpenCode Runner: ❌ Aborted"));
+        assert!(markdown.contains("**Reason:** Stuck in a loop"));
+        assert!(markdown.contains("| # | Time | Decision | Reason | Lines | Retries |"));
+        assert!(markdown.contains("✓ Continue | Making progress | 40 | 1 |"));
+        assert!(markdown.contains("✗ Abort | Stuck in a loop | 12 | 0 |"));
+        assert!(markdown.contains("Activity log"));
+    }
+
+    #[test]
+    fn test_markdown_lists_verification() {
+        let mut state = test_state();
+        state.record_verification(vec![VerificationResult {
+            command: "cargo test".to_string(),
+            success: false,
+            exit_code: Some(101),
+            output: "1 test failed".to_string(),
+            duration_ms: 2500,
+        }]);
+        let report = RunReport::new(&test_config(), None, &state, &RunResult::MaxIterations);
+
+        assert_eq!(report.iterations[1].verification.len(), 1);
+        let markdown = report.to_markdown();
+        assert!(markdown.contains("### Verification"));
+        assert!(markdown.contains("- Iteration 2: `cargo test` failed (exit 101) in 2.5s"));
+        assert!(report
+            .activity_log
+            .contains("verify: `cargo test` failed (exit 101)"));
+    }
+
+    #[test]
+    fn test_markdown_escapes_table_cells() {
+        let mut state = State::new();
+        state.start_iteration();
+        state.record_decision(
+            1,
+            ReviewerDecision {
+                action: ReviewerAction::Continue,
+                reason: "a | b\nc".to_string(),
+            },
+            0,
+        );
+        let report = RunReport::new(&test_config(), None, &state, &RunResult::Completed);
+
+        assert!(report.to_markdown().contains("| a \\| b c |"));
+    }
+
+    #[test]
+    fn test_junit_failure_for_aborted_run() {
+        let state = test_state();
+        let result = RunResult::Aborted("Stuck <in> a \"loop\"".to_string());
+        let report = RunReport::new(&test_config(), None, &state, &result);
+
+        let xml = junit_xml(&[report], &[]);
+        assert!(xml.starts_with("<?xml"));
+        assert!(xml.contains("tests=\"1\" failures=\"1\""));
+        assert!(xml.contains("<testcase classname=\"opencode_runner\" name=\"Fix the build\""));
+        assert!(xml.contains(
+            "<failure type=\"aborted\" message=\"Stuck &lt;in&gt; a &quot;loop&quot;\">"
+        ));
+    }
+
+    #[test]
+    fn test_junit_passed_and_skipped_runs() {
+        let state = test_state();
+        let passed = RunReport::new(&test_config(), None, &state, &RunResult::Completed);
+        let skipped = RunReport::new(&test_config(), None, &state, &RunResult::Interrupted);
+
+        let xml = junit_xml(&[passed, skipped], &[]);
+        assert!(xml.contains("tests=\"2\" failures=\"0\" errors=\"0\" skipped=\"1\""));
+        assert!(!xml.contains("<failure"));
+        assert!(xml.contains("<skipped message=\"Interrupted by user\"/>"));
+    }
+
+    #[test]
+    fn test_write_markdown_and_junit() {
+        let dir = tempfile::tempdir().unwrap();
+        let report = RunReport::new(
+            &test_config(),
+            None,
+            &test_state(),
+            &RunResult::MaxIterations,
+        );
+
+        report.write_markdown(&dir.path().join("run.md")).unwrap();
+        report.write_junit(&dir.path().join("junit.xml")).unwrap();
+
+        let xml = std::fs::read_to_string(dir.path().join("junit.xml")).unwrap();
+        assert!(
+            xml.contains("<failure type=\"max_iterations\" message=\"Max iterations reached\">")
+        );
+        assert!(dir.path().join("run.md").exists());
+    }
+}
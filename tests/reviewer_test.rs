@@ -1,7 +1,17 @@
+mod support;
+
+use opencode_runner::git::TreeDiff;
 use opencode_runner::reviewer;
 use opencode_runner::reviewer::{
-    ReviewerAction, ReviewerClient, ReviewerContext, ReviewerDecision,
+    estimate_tokens, parse_decision, trim_middle, truncate_long_lines, HeuristicReviewer,
+    ParseStrategy, PromptBudget, PromptTemplate, RateLimitedReviewer, RateLimiter, RetryAfter,
+    Reviewer, ReviewerAction, ReviewerBackend, ReviewerClient, ReviewerContext, ReviewerDecision,
+    ReviewerOptions, ScriptedReviewer,
 };
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use support::mock_openai::{MockOpenAi, Reply};
 
 #[cfg(test)]
 mod tests {
@@ -19,6 +29,9 @@ mod tests {
             iteration,
             previous_summaries,
             current_sample: current_sample.to_string(),
+            worker_idle: false,
+            verification: Vec::new(),
+            diff: None,
         }
     }
 
@@ -79,7 +92,7 @@ mod tests {
 
         match decision.action {
             ReviewerAction::Continue => assert!(true),
-            ReviewerAction::Abort => panic!("Expected Continue, got Abort"),
+            other => panic!("Expected Continue, got {:?}", other),
         }
         assert_eq!(decision.reason, "Making progress");
     }
@@ -91,7 +104,7 @@ mod tests {
 
         match decision.action {
             ReviewerAction::Abort => assert!(true),
-            ReviewerAction::Continue => panic!("Expected Abort, got Continue"),
+            other => panic!("Expected Abort, got {:?}", other),
         }
         assert_eq!(decision.reason, "Stuck in loop");
     }
@@ -291,6 +304,9 @@ mod tests {
             iteration: 0,
             previous_summaries: vec![],
             current_sample: String::new(),
+            worker_idle: false,
+            verification: Vec::new(),
+            diff: None,
         };
 
         assert_eq!(context.task_description, "");
@@ -346,4 +362,652 @@ mod tests {
         assert!(summary.contains("Iter 5: Abort"));
         assert!(summary.contains("This is a very long reason that contains many details about why the assistant is stuck in a loop"));
     }
+
+    #[tokio::test]
+    async fn test_scripted_reviewer_replays_decisions() {
+        let reviewer = ScriptedReviewer::new(vec![
+            ReviewerDecision {
+                action: ReviewerAction::Continue,
+                reason: "First".to_string(),
+            },
+            ReviewerDecision {
+                action: ReviewerAction::Abort,
+                reason: "Second".to_string(),
+            },
+        ]);
+
+        let context = create_test_context("Task", 1, vec![], "Output");
+        let first = reviewer.review_with_retry(&context).await.unwrap().decision;
+        let second = reviewer.review_with_retry(&context).await.unwrap().decision;
+        let exhausted = reviewer.review_with_retry(&context).await.unwrap().decision;
+
+        assert_eq!(first.reason, "First");
+        assert_eq!(second.action, ReviewerAction::Abort);
+        assert_eq!(exhausted.action, ReviewerAction::Continue);
+        assert_eq!(reviewer.remaining(), 0);
+        assert_eq!(reviewer.contexts().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_heuristic_reviewer_aborts_on_repeated_output() {
+        let reviewer = HeuristicReviewer::new();
+        let sample = vec!["Trying again..."; 12].join("\n");
+        let context = create_test_context("Task", 1, vec![], &sample);
+
+        let decision = reviewer.review_with_retry(&context).await.unwrap().decision;
+        assert_eq!(decision.action, ReviewerAction::Abort);
+    }
+
+    #[tokio::test]
+    async fn test_heuristic_reviewer_continues_on_varied_output() {
+        let reviewer = HeuristicReviewer::new();
+        let sample = (1..=12)
+            .map(|i| format!("Edited file_{}.rs", i))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let context = create_test_context("Task", 1, vec![], &sample);
+
+        let decision = reviewer.review_with_retry(&context).await.unwrap().decision;
+        assert_eq!(decision.action, ReviewerAction::Continue);
+    }
+
+    #[test]
+    fn test_backend_builds_named_reviewer() {
+        let reviewer = ReviewerBackend::Heuristic
+            .build("http://localhost:11434".to_string(), "llama3".to_string())
+            .unwrap();
+        assert_eq!(reviewer.name(), "heuristic");
+
+        let reviewer = ReviewerBackend::OpenAi
+            .build("http://localhost:11434".to_string(), "llama3".to_string())
+            .unwrap();
+        assert_eq!(reviewer.name(), "openai");
+    }
+
+    /// Reviewer that takes a while and records how many reviews overlap
+    struct SlowReviewer {
+        in_flight: AtomicUsize,
+        max_in_flight: AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl Reviewer for SlowReviewer {
+        fn name(&self) -> &str {
+            "slow"
+        }
+
+        async fn review(&self, _context: &ReviewerContext) -> anyhow::Result<ReviewerDecision> {
+            let now = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_in_flight.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            Ok(ReviewerDecision {
+                action: ReviewerAction::Continue,
+                reason: "Slow".to_string(),
+            })
+        }
+    }
+
+    /// Reviewer whose first attempt fails, optionally asking for a delay
+    struct FlakyReviewer {
+        attempts: AtomicUsize,
+        retry_after: Option<Duration>,
+    }
+
+    #[async_trait::async_trait]
+    impl Reviewer for FlakyReviewer {
+        fn name(&self) -> &str {
+            "flaky"
+        }
+
+        async fn review(&self, _context: &ReviewerContext) -> anyhow::Result<ReviewerDecision> {
+            if self.attempts.fetch_add(1, Ordering::SeqCst) == 0 {
+                return Err(match self.retry_after {
+                    Some(delay) => anyhow::Error::new(RetryAfter(delay)),
+                    None => anyhow::anyhow!("Flaky"),
+                });
+            }
+            Ok(ReviewerDecision {
+                action: ReviewerAction::Continue,
+                reason: "Recovered".to_string(),
+            })
+        }
+
+        fn retry_policy(&self) -> (u8, Duration) {
+            (3, Duration::from_millis(1))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rate_limiter_caps_concurrent_reviews() {
+        let inner = Arc::new(SlowReviewer {
+            in_flight: AtomicUsize::new(0),
+            max_in_flight: AtomicUsize::new(0),
+        });
+        let limiter = Arc::new(RateLimiter::new(2, Duration::ZERO));
+        let reviewers: Vec<_> = (0..5)
+            .map(|_| RateLimitedReviewer::new(inner.clone(), limiter.clone()))
+            .collect();
+        let context = create_test_context("Task", 1, vec![], "Output");
+
+        let reviews = reviewers.iter().map(|r| r.review_with_retry(&context));
+        let decisions = futures::future::join_all(reviews).await;
+
+        assert!(decisions.iter().all(|d| d.is_ok()));
+        assert_eq!(inner.max_in_flight.load(Ordering::SeqCst), 2);
+        assert_eq!(reviewers[0].name(), "slow");
+    }
+
+    #[tokio::test]
+    async fn test_rate_limiter_spaces_requests() {
+        let limiter = Arc::new(RateLimiter::new(4, Duration::from_millis(30)));
+        let reviewer = RateLimitedReviewer::new(Arc::new(ScriptedReviewer::new(vec![])), limiter);
+        let context = create_test_context("Task", 1, vec![], "Output");
+
+        let start = Instant::now();
+        for _ in 0..3 {
+            reviewer.review_with_retry(&context).await.unwrap();
+        }
+
+        // The second and third requests each wait for the interval
+        assert!(start.elapsed() >= Duration::from_millis(60));
+    }
+
+    #[tokio::test]
+    async fn test_rate_limiter_spaces_retries() {
+        let inner = Arc::new(FlakyReviewer {
+            attempts: AtomicUsize::new(0),
+            retry_after: None,
+        });
+        let limiter = Arc::new(RateLimiter::new(1, Duration::from_millis(200)));
+        let reviewer = RateLimitedReviewer::new(inner.clone(), limiter);
+        let context = create_test_context("Task", 1, vec![], "Output");
+
+        let start = Instant::now();
+        let outcome = reviewer.review_with_retry(&context).await.unwrap();
+
+        assert_eq!(outcome.decision.reason, "Recovered");
+        assert_eq!(outcome.retries, 1);
+        assert_eq!(inner.attempts.load(Ordering::SeqCst), 2);
+        // The retry waits for the interval, not just the 1ms backoff
+        assert!(start.elapsed() >= Duration::from_millis(200));
+    }
+
+    #[tokio::test]
+    async fn test_rate_limiter_defers_on_retry_after() {
+        let inner = Arc::new(FlakyReviewer {
+            attempts: AtomicUsize::new(0),
+            retry_after: Some(Duration::from_millis(300)),
+        });
+        let limiter = Arc::new(RateLimiter::new(4, Duration::ZERO));
+        let flaky = RateLimitedReviewer::new(inner, limiter.clone());
+        let other = RateLimitedReviewer::new(Arc::new(ScriptedReviewer::new(vec![])), limiter);
+        let context = create_test_context("Task", 1, vec![], "Output");
+
+        assert!(flaky.review(&context).await.is_err());
+
+        // Other runs sharing the limiter also wait out the requested delay
+        let start = Instant::now();
+        other.review_with_retry(&context).await.unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(250));
+    }
+
+    fn long_sample(lines: usize) -> String {
+        (1..=lines)
+            .map(|i| format!("line {}: compiling module and running checks", i))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn test_budget_leaves_small_prompt_alone() {
+        let context = create_test_context("Task", 2, vec!["Iteration 1: ok".to_string()], "Output");
+        let fitted = PromptBudget::new(10_000).fit(&context, reviewer::build_prompt);
+
+        assert_eq!(
+            reviewer::build_prompt(&fitted),
+            reviewer::build_prompt(&context)
+        );
+    }
+
+    #[test]
+    fn test_budget_trims_sample_from_middle() {
+        let context = create_test_context("Task", 1, vec![], &long_sample(2000));
+        let fitted = PromptBudget::new(1500).fit(&context, reviewer::build_prompt);
+        let prompt = reviewer::build_prompt(&fitted);
+
+        assert!(estimate_tokens(&prompt) <= 1500);
+        assert!(prompt.contains("line 1: compiling"));
+        assert!(prompt.contains("line 2000: compiling"));
+        assert!(prompt.contains("lines omitted] ..."));
+    }
+
+    #[test]
+    fn test_budget_keeps_newest_summaries() {
+        let summaries: Vec<String> = (1..=5)
+            .map(|i| {
+                format!(
+                    "Iteration {}: {}",
+                    i,
+                    "still refactoring the parser ".repeat(10)
+                )
+            })
+            .collect();
+        let context = create_test_context("Task", 6, summaries.clone(), &long_sample(100));
+        let fitted = PromptBudget::new(900).fit(&context, reviewer::build_prompt);
+
+        let kept = &fitted.previous_summaries;
+        assert!(kept.len() >= 2);
+        // The newest summaries are kept word for word
+        assert_eq!(kept[kept.len() - 2..], summaries[3..]);
+        for summary in &kept[..kept.len() - 2] {
+            assert!(summary.ends_with("..."));
+            assert!(summary.len() < 100);
+        }
+        assert!(estimate_tokens(&reviewer::build_prompt(&fitted)) <= 900);
+    }
+
+    #[test]
+    fn test_budget_counts_system_prompt() {
+        let context = create_test_context("Task", 1, vec![], &long_sample(2000));
+        let system_prompt = "You are a strict reviewer. ".repeat(40);
+        let template = PromptTemplate::default();
+        let prompt = reviewer::build_prompt_within(
+            &context,
+            &template,
+            &system_prompt,
+            Some(PromptBudget::new(2000)),
+        );
+
+        assert!(estimate_tokens(&prompt) + estimate_tokens(&system_prompt) <= 2000);
+        assert_eq!(
+            reviewer::build_prompt_within(&context, &template, &system_prompt, None),
+            reviewer::build_prompt(&context)
+        );
+    }
+
+    #[test]
+    fn test_truncate_long_lines() {
+        let text = format!("short\n{}", "x".repeat(500));
+        let truncated = truncate_long_lines(&text, 400);
+
+        assert!(truncated.starts_with("short\n"));
+        assert!(truncated.ends_with(" ... [100 more chars]"));
+        assert_eq!(truncate_long_lines("a\nb", 400), "a\nb");
+    }
+
+    #[test]
+    fn test_trim_middle_keeps_head_and_tail() {
+        let text = long_sample(100);
+        let trimmed = trim_middle(&text, 1000);
+
+        assert!(trimmed.len() <= 1000);
+        assert!(trimmed.starts_with("line 1: "));
+        assert!(trimmed.ends_with("line 100: compiling module and running checks"));
+        assert!(trimmed.contains("lines omitted] ..."));
+        assert_eq!(trim_middle("small", 1000), "small");
+    }
+
+    #[test]
+    fn test_custom_prompt_template() {
+        let template = PromptTemplate::parse(
+            "Review iteration {{ iteration }} of: {{task}}\n{{summaries}}\n---\n{{sample}}",
+        )
+        .unwrap();
+        let context = create_test_context(
+            "Fix the parser",
+            3,
+            vec!["Iteration 2: ok".to_string()],
+            "cargo build",
+        );
+
+        assert_eq!(
+            template.render(&context),
+            "Review iteration 3 of: Fix the parser\n1. Iteration 2: ok\n---\ncargo build"
+        );
+    }
+
+    #[test]
+    fn test_diff_is_an_alias_of_changes() {
+        let mut context = create_test_context("Fix the parser", 2, vec![], "cargo test");
+        context.diff = Some(TreeDiff {
+            stat: " src/parser.rs | 2 +-".to_string(),
+            patch: "-let x = 1;\n+let x = 2;".to_string(),
+            ..TreeDiff::default()
+        });
+
+        let changes = PromptTemplate::parse("{{task}}{{changes}}\n{{sample}}").unwrap();
+        let diff = PromptTemplate::parse("{{task}}{{ diff }}\n{{sample}}").unwrap();
+
+        let prompt = diff.render(&context);
+        assert_eq!(prompt, changes.render(&context));
+        assert!(prompt.contains("src/parser.rs | 2 +-"));
+    }
+
+    #[test]
+    fn test_default_template_matches_build_prompt() {
+        let context = create_test_context("Task", 1, vec![], "Output");
+        let prompt = PromptTemplate::default().render(&context);
+
+        assert_eq!(prompt, reviewer::build_prompt(&context));
+        assert!(prompt.contains("Task: Task"));
+        assert!(prompt.contains("\"action\": \"continue|abort|redirect|complete\""));
+    }
+
+    #[test]
+    fn test_prompt_template_validation() {
+        let err = PromptTemplate::parse("{{task}}\n{{sample}} {{output}}").unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("Unknown placeholder {{output}} on line 2"));
+        assert!(message.contains("{{changes}}"));
+
+        let err = PromptTemplate::parse("Only {{task}}").unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("missing required placeholders: {{sample}}"));
+
+        let err = PromptTemplate::parse("{{task}} {{sample").unwrap_err();
+        assert!(err.to_string().contains("Unclosed placeholder on line 1"));
+
+        // Single braces are left alone
+        assert!(PromptTemplate::parse("{\"task\": \"{{task}}\"} {{sample}}").is_ok());
+    }
+
+    #[test]
+    fn test_reviewer_options_from_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let system_path = dir.path().join("system.txt");
+        let template_path = dir.path().join("review.txt");
+        std::fs::write(&system_path, "Be strict about tests.\n").unwrap();
+        std::fs::write(&template_path, "{{task}}: {{sample}}").unwrap();
+
+        let options = ReviewerOptions::load(
+            Some("Inline prompt"),
+            Some(&system_path),
+            Some(&template_path),
+            Some(4000),
+        )
+        .unwrap();
+        assert_eq!(
+            options.system_prompt.as_deref(),
+            Some("Be strict about tests.")
+        );
+        assert_eq!(options.token_budget, Some(4000));
+        let context = create_test_context("Task", 1, vec![], "Output");
+        assert_eq!(
+            options.prompt_template.unwrap().render(&context),
+            "Task: Output"
+        );
+
+        std::fs::write(&template_path, "{{task}}").unwrap();
+        let err = ReviewerOptions::load(None, None, Some(&template_path), None).unwrap_err();
+        assert!(format!("{:#}", err).contains("Invalid prompt template"));
+        assert!(
+            ReviewerOptions::load(None, Some(&dir.path().join("missing.txt")), None, None).is_err()
+        );
+    }
+
+    /// Client for `mock` with millisecond backoff and a short request timeout
+    fn mock_client(mock: &MockOpenAi) -> ReviewerClient {
+        let mut client = ReviewerClient::new(mock.base_url(), "mock-model".to_string());
+        client.retry_base_delay = Duration::from_millis(10);
+        client.http_client = reqwest::Client::builder()
+            .timeout(Duration::from_millis(500))
+            .build()
+            .unwrap();
+        client
+    }
+
+    #[tokio::test]
+    async fn test_mock_reviewer_parses_decision() {
+        let mock = MockOpenAi::start(vec![Reply::Content(
+            r#"{"action": "redirect", "guidance": "Run the tests", "reason": "Untested"}"#
+                .to_string(),
+        )])
+        .await;
+        let client = mock_client(&mock);
+        let context = create_test_context("Fix the parser", 2, vec![], "Editing parser.rs");
+
+        let decision = client.review_with_retry(&context).await.unwrap().decision;
+
+        assert_eq!(
+            decision.action,
+            ReviewerAction::Redirect {
+                guidance: "Run the tests".to_string()
+            }
+        );
+        assert_eq!(decision.reason, "Untested");
+
+        let requests = mock.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0]["model"], "mock-model");
+        assert_eq!(requests[0]["messages"][0]["role"], "system");
+        assert_eq!(requests[0]["response_format"]["type"], "json_object");
+        let prompt = requests[0]["messages"][1]["content"].as_str().unwrap();
+        assert!(prompt.contains("Fix the parser"));
+        assert!(prompt.contains("Editing parser.rs"));
+    }
+
+    #[tokio::test]
+    async fn test_mock_reviewer_retries_malformed_replies() {
+        let mock = MockOpenAi::start(vec![
+            Reply::Content("I am not sure yet".to_string()),
+            Reply::Raw("not a chat completion".to_string()),
+            Reply::decision("abort", "Looping"),
+        ])
+        .await;
+        let client = mock_client(&mock);
+        let context = create_test_context("Task", 1, vec![], "Output");
+
+        let outcome = client.review_with_retry(&context).await.unwrap();
+
+        assert_eq!(outcome.decision.action, ReviewerAction::Abort);
+        assert_eq!(outcome.decision.reason, "Looping");
+        assert_eq!(outcome.retries, 2);
+        assert_eq!(mock.request_count(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_mock_reviewer_falls_back_to_continue() {
+        let mock = MockOpenAi::start(vec![
+            Reply::Status(500),
+            Reply::Status(503),
+            Reply::Status(429),
+        ])
+        .await;
+        let client = mock_client(&mock);
+        let context = create_test_context("Task", 1, vec![], "Output");
+
+        let start = Instant::now();
+        let outcome = client.review_with_retry(&context).await.unwrap();
+        let decision = outcome.decision;
+
+        assert_eq!(decision.action, ReviewerAction::Continue);
+        assert!(decision.reason.contains("unavailable after 3 retries"));
+        assert_eq!(outcome.retries, 2);
+        assert_eq!(mock.request_count(), 3);
+        // Two retries of 10ms and 20ms, no sleep after the last attempt
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn test_mock_reviewer_backoff_doubles() {
+        let mock = MockOpenAi::start(vec![
+            Reply::Status(500),
+            Reply::Status(500),
+            Reply::decision("continue", "Progressing"),
+        ])
+        .await;
+        let mut client = mock_client(&mock);
+        client.retry_base_delay = Duration::from_millis(100);
+        let context = create_test_context("Task", 1, vec![], "Output");
+
+        let start = Instant::now();
+        let decision = client.review_with_retry(&context).await.unwrap().decision;
+
+        assert_eq!(decision.action, ReviewerAction::Continue);
+        assert_eq!(decision.reason, "Progressing");
+        assert!(start.elapsed() >= Duration::from_millis(300));
+    }
+
+    #[tokio::test]
+    async fn test_mock_reviewer_honours_retry_after() {
+        let mock = MockOpenAi::start(vec![
+            Reply::RateLimited(1),
+            Reply::decision("continue", "Progressing"),
+        ])
+        .await;
+        let client = mock_client(&mock);
+        let context = create_test_context("Task", 1, vec![], "Output");
+
+        let start = Instant::now();
+        let outcome = client.review_with_retry(&context).await.unwrap();
+
+        assert_eq!(outcome.decision.reason, "Progressing");
+        assert_eq!(outcome.retries, 1);
+        // Waits the requested second rather than the 10ms backoff
+        assert!(start.elapsed() >= Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn test_mock_reviewer_times_out_hung_request() {
+        let mock = MockOpenAi::start(vec![Reply::Hang, Reply::decision("complete", "Done")]).await;
+        let client = mock_client(&mock);
+        let context = create_test_context("Task", 1, vec![], "Output");
+
+        let decision = client.review_with_retry(&context).await.unwrap().decision;
+
+        assert_eq!(decision.action, ReviewerAction::Complete);
+        assert_eq!(mock.request_count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_mock_reviewer_waits_for_slow_reply() {
+        let mock = MockOpenAi::start(vec![Reply::Slow(
+            Duration::from_millis(200),
+            r#"{"action": "complete", "reason": "Done"}"#.to_string(),
+        )])
+        .await;
+        let client = mock_client(&mock);
+        let context = create_test_context("Task", 1, vec![], "Output");
+
+        let decision = client.review_with_retry(&context).await.unwrap().decision;
+
+        assert_eq!(decision.action, ReviewerAction::Complete);
+        assert_eq!(mock.request_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_mock_reviewer_accepts_fenced_reply() {
+        let mock = MockOpenAi::start(vec![Reply::Content(
+            "```json\n{\"action\": \"Abort\", \"reason\": \"Looping\"}\n```".to_string(),
+        )])
+        .await;
+        let client = mock_client(&mock);
+        let context = create_test_context("Task", 1, vec![], "Output");
+
+        let outcome = client.review_with_retry(&context).await.unwrap();
+
+        assert_eq!(outcome.decision.action, ReviewerAction::Abort);
+        assert_eq!(outcome.retries, 0);
+        assert_eq!(mock.request_count(), 1);
+    }
+
+    #[test]
+    fn test_parse_strict_json() {
+        let parsed = parse_decision(r#"{"action": "continue", "reason": "Progressing"}"#).unwrap();
+
+        assert_eq!(parsed.strategy, ParseStrategy::Strict);
+        assert_eq!(parsed.decision.action, ReviewerAction::Continue);
+        assert_eq!(parsed.decision.reason, "Progressing");
+    }
+
+    #[test]
+    fn test_parse_extracts_json_from_fences_and_prose() {
+        let fenced = "```json\n{\"action\": \"abort\", \"reason\": \"Looping\"}\n```";
+        let parsed = parse_decision(fenced).unwrap();
+        assert_eq!(parsed.strategy, ParseStrategy::Extracted);
+        assert_eq!(parsed.decision.action, ReviewerAction::Abort);
+
+        let prose = "Here is my decision: {\"action\": \"redirect\", \"reason\": \"Off track\", \
+                     \"guidance\": \"Focus on {the} tests\"} Hope that helps.";
+        let parsed = parse_decision(prose).unwrap();
+        assert_eq!(parsed.strategy, ParseStrategy::Extracted);
+        assert_eq!(
+            parsed.decision.action,
+            ReviewerAction::Redirect {
+                guidance: "Focus on {the} tests".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_normalizes_action_names() {
+        let cases = [
+            ("Continue", ReviewerAction::Continue),
+            ("PROCEED", ReviewerAction::Continue),
+            ("ABORT", ReviewerAction::Abort),
+            ("stop", ReviewerAction::Abort),
+            ("halt", ReviewerAction::Abort),
+            ("Complete", ReviewerAction::Complete),
+            ("Done", ReviewerAction::Complete),
+            ("finished", ReviewerAction::Complete),
+        ];
+        for (name, expected) in cases {
+            let reply = format!(r#"{{"action": "{}", "reason": "Because"}}"#, name);
+            let parsed = parse_decision(&reply).unwrap();
+            assert_eq!(parsed.strategy, ParseStrategy::Extracted, "{}", name);
+            assert_eq!(parsed.decision.action, expected, "{}", name);
+        }
+
+        let parsed = parse_decision(r#"{"action": "complete"}"#).unwrap();
+        assert_eq!(parsed.decision.reason, "No reason given");
+
+        assert!(parse_decision(r#"{"action": "maybe", "reason": "Because"}"#).is_err());
+    }
+
+    #[test]
+    fn test_parse_repairs_trailing_commas() {
+        let reply = "{\"action\": \"complete\", \"reason\": \"Tests pass, done\",\n}";
+        let parsed = parse_decision(reply).unwrap();
+
+        assert_eq!(parsed.strategy, ParseStrategy::Repaired);
+        assert_eq!(parsed.decision.action, ReviewerAction::Complete);
+        assert_eq!(parsed.decision.reason, "Tests pass, done");
+    }
+
+    #[test]
+    fn test_parse_falls_back_to_keywords() {
+        let parsed = parse_decision("Abort: the assistant is stuck. Abort now.").unwrap();
+        assert_eq!(parsed.strategy, ParseStrategy::Keywords);
+        assert_eq!(parsed.decision.action, ReviewerAction::Abort);
+        assert!(parsed.decision.reason.contains("stuck"));
+
+        let parsed = parse_decision("Let it continue, it is making progress.").unwrap();
+        assert_eq!(parsed.decision.action, ReviewerAction::Continue);
+
+        // Synonyms count as their action
+        let parsed = parse_decision("The assistant is stuck repeating itself.").unwrap();
+        assert_eq!(parsed.strategy, ParseStrategy::Keywords);
+        assert_eq!(parsed.decision.action, ReviewerAction::Abort);
+        assert!(parsed.decision.reason.contains("stuck repeating"));
+
+        let parsed = parse_decision("Proceed, the worker is progressing.").unwrap();
+        assert_eq!(parsed.decision.action, ReviewerAction::Continue);
+    }
+
+    #[test]
+    fn test_parse_rejects_unclassifiable_reply() {
+        let err = parse_decision("I am not sure yet").unwrap_err();
+        assert!(err.to_string().contains("I am not sure yet"));
+
+        assert!(parse_decision("Please redirect the worker").is_err());
+        // Completion is never guessed from text
+        assert!(parse_decision("The task is complete.").is_err());
+        assert!(parse_decision("The worker is done.").is_err());
+        // More than one action named is ambiguous, synonyms included
+        assert!(parse_decision("Should I continue or abort?").is_err());
+        assert!(parse_decision("Proceed unless it gets stuck.").is_err());
+        assert!(parse_decision(r#"{"action": "redirect"} then continue"#).is_err());
+    }
 }
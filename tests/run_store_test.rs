@@ -0,0 +1,119 @@
+use chrono::Utc;
+use opencode_runner::{
+    config::ControlConfig,
+    reviewer::{ReviewerAction, ReviewerBackend, ReviewerDecision},
+    run_store::{generate_run_id, RunMeta, RunStore},
+    state::State,
+};
+use std::path::PathBuf;
+use std::time::Duration;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_meta(run_id: &str) -> RunMeta {
+        RunMeta {
+            run_id: run_id.to_string(),
+            session_id: Some("ses_123".to_string()),
+            server_url: "http://127.0.0.1:4096".to_string(),
+            working_dir: PathBuf::from("/tmp/project"),
+            worker_model: "ollama/llama3.1".to_string(),
+            reviewer_backend: ReviewerBackend::Heuristic,
+            reviewer_url: "http://localhost:11434/v1".to_string(),
+            reviewer_model: "llama3".to_string(),
+            reviewer_system_prompt: None,
+            reviewer_system_prompt_file: None,
+            reviewer_prompt_template: None,
+            reviewer_token_budget: None,
+            sample_lines: 100,
+            config: ControlConfig::new("Fix the build".to_string(), 10, Duration::from_secs(30)),
+            worktree: None,
+            checkpoints: false,
+            record_events: false,
+            verify: Vec::new(),
+            verify_timeout: 600,
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_save_and_load_roundtrip() {
+        let runs_dir = tempfile::tempdir().unwrap();
+        let store = RunStore::create(runs_dir.path(), "run-1").unwrap();
+
+        let mut state = State::new();
+        state.start_iteration();
+        state.record_decision(
+            42,
+            ReviewerDecision {
+                action: ReviewerAction::Continue,
+                reason: "Making progress".to_string(),
+            },
+            1,
+        );
+        state.start_iteration();
+        state.record_decision(
+            7,
+            ReviewerDecision {
+                action: ReviewerAction::Redirect {
+                    guidance: "Focus on the failing test".to_string(),
+                },
+                reason: "Wandering".to_string(),
+            },
+            0,
+        );
+
+        store.save(&test_meta("run-1"), &state).unwrap();
+
+        let reopened = RunStore::open(runs_dir.path(), "run-1").unwrap();
+        let (meta, loaded) = reopened.load().unwrap();
+
+        assert_eq!(meta.run_id, "run-1");
+        assert_eq!(meta.session_id.as_deref(), Some("ses_123"));
+        assert_eq!(meta.reviewer_backend, ReviewerBackend::Heuristic);
+        assert_eq!(meta.config.task, "Fix the build");
+        assert_eq!(meta.config.inactivity_timeout, Duration::from_secs(30));
+
+        assert_eq!(loaded.current_iteration(), 2);
+        assert_eq!(loaded.iterations().len(), 2);
+        assert_eq!(loaded.iterations()[0].sample_size, 42);
+        assert_eq!(loaded.iterations()[0].reviewer_retry_count, 1);
+        assert_eq!(
+            loaded.iterations()[1].decision.action,
+            ReviewerAction::Redirect {
+                guidance: "Focus on the failing test".to_string()
+            }
+        );
+        assert_eq!(loaded.start_time(), state.start_time());
+    }
+
+    #[test]
+    fn test_save_overwrites_previous_record() {
+        let runs_dir = tempfile::tempdir().unwrap();
+        let store = RunStore::create(runs_dir.path(), "run-2").unwrap();
+
+        let mut state = State::new();
+        store.save(&test_meta("run-2"), &state).unwrap();
+
+        state.start_iteration();
+        store.save(&test_meta("run-2"), &state).unwrap();
+
+        let (_, loaded) = store.load().unwrap();
+        assert_eq!(loaded.current_iteration(), 1);
+    }
+
+    #[test]
+    fn test_open_missing_run_fails() {
+        let runs_dir = tempfile::tempdir().unwrap();
+        assert!(RunStore::open(runs_dir.path(), "does-not-exist").is_err());
+    }
+
+    #[test]
+    fn test_generated_run_ids_are_path_safe() {
+        let run_id = generate_run_id();
+        assert!(!run_id.is_empty());
+        assert!(!run_id.contains('/'));
+        assert!(!run_id.contains(' '));
+    }
+}
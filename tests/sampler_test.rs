@@ -1,4 +1,4 @@
-use opencode_runner::sampler::{Sampler, SamplerEvent};
+use opencode_runner::sampler::{tool_line, Sampler, SamplerEvent};
 
 #[cfg(test)]
 mod tests {
@@ -178,4 +178,93 @@ mod tests {
         assert!(sample.contains("emojis"));
         assert!(sample.contains("unicode"));
     }
+
+    // Test backfilling lines missed while disconnected
+    #[test]
+    fn test_sampler_backfill_after_last_seen_line() {
+        let mut sampler = Sampler::new(10);
+        sampler.add_line("Line 1");
+        sampler.add_line("Line 2");
+
+        let history: Vec<String> = (1..=4).map(|i| format!("Line {}", i)).collect();
+        let added = sampler.backfill(&history);
+
+        assert_eq!(added, 2);
+        assert_eq!(sampler.sample(), "Line 1\nLine 2\nLine 3\nLine 4");
+    }
+
+    // Test backfilling after the buffer was cleared for a new iteration
+    #[test]
+    fn test_sampler_backfill_after_clear() {
+        let mut sampler = Sampler::new(10);
+        sampler.add_line("Line 1");
+        sampler.add_line("Line 2");
+        sampler.clear();
+
+        let history: Vec<String> = (1..=3).map(|i| format!("Line {}", i)).collect();
+        let added = sampler.backfill(&history);
+
+        assert_eq!(added, 1);
+        assert_eq!(sampler.sample(), "Line 3");
+    }
+
+    // Test backfilling when nothing was seen yet
+    #[test]
+    fn test_sampler_backfill_empty_sampler() {
+        let mut sampler = Sampler::new(2);
+
+        let history: Vec<String> = (1..=3).map(|i| format!("Line {}", i)).collect();
+        let added = sampler.backfill(&history);
+
+        assert_eq!(added, 3);
+        assert_eq!(sampler.sample(), "Line 2\nLine 3");
+    }
+
+    // Test backfilling when nothing was missed
+    #[test]
+    fn test_sampler_backfill_nothing_missed() {
+        let mut sampler = Sampler::new(10);
+        sampler.add_line("Line 1");
+
+        let added = sampler.backfill(&["Line 1".to_string()]);
+
+        assert_eq!(added, 0);
+        assert_eq!(sampler.line_count(), 1);
+    }
+
+    // Test backfilling when the last seen line isn't in the history
+    #[test]
+    fn test_sampler_backfill_last_line_not_found() {
+        let mut sampler = Sampler::new(3);
+        sampler.add_line("Line 1");
+        sampler.add_line("[Tool: git status]");
+
+        let history: Vec<String> = (1..=20).map(|i| format!("Line {}", i)).collect();
+        let added = sampler.backfill(&history);
+
+        // Only a bounded tail is added, not the whole session again
+        assert_eq!(added, 3);
+        assert_eq!(sampler.sample(), "Line 18\nLine 19\nLine 20");
+    }
+
+    // Test that tool lines from the stream match those from history
+    #[test]
+    fn test_sampler_backfill_after_tool_line() {
+        let mut sampler = Sampler::new(10);
+        sampler.add_line("Reading the config");
+        sampler.add_line(&tool_line("read", Some("config.toml")));
+
+        let history = vec![
+            "Reading the config".to_string(),
+            "[Tool: read config.toml]".to_string(),
+            "Found the bug".to_string(),
+        ];
+        let added = sampler.backfill(&history);
+
+        assert_eq!(added, 1);
+        assert_eq!(
+            sampler.sample(),
+            "Reading the config\n[Tool: read config.toml]\nFound the bug"
+        );
+    }
 }
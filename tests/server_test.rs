@@ -0,0 +1,32 @@
+use opencode_runner::server::ServerManager;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_attach_parses_port() {
+        let server = ServerManager::attach("http://127.0.0.1:4096/").unwrap();
+
+        assert_eq!(server.port(), 4096);
+        assert_eq!(server.base_url(), "http://127.0.0.1:4096");
+        assert!(!server.is_owned());
+    }
+
+    #[test]
+    fn test_attach_uses_default_port() {
+        let server = ServerManager::attach("https://opencode.internal").unwrap();
+        assert_eq!(server.port(), 443);
+    }
+
+    #[test]
+    fn test_attach_rejects_invalid_url() {
+        assert!(ServerManager::attach("not a url").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_leaves_attached_server_alone() {
+        let server = ServerManager::attach("http://127.0.0.1:4096").unwrap();
+        assert!(server.shutdown().await.is_ok());
+    }
+}
@@ -0,0 +1,143 @@
+// Stand-in for an OpenAI-compatible `/chat/completions` endpoint, for
+// reviewer tests that need exact control over what the API returns.
+//
+// Each request takes the next scripted reply; once the script runs out
+// every request gets a 500.
+
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::post,
+    Json, Router,
+};
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+/// How the mock answers one request
+#[derive(Debug, Clone)]
+pub enum Reply {
+    /// 200 with `content` as the assistant message
+    Content(String),
+    /// 200 with `body` as the whole response body
+    Raw(String),
+    /// Error status with a short body
+    Status(u16),
+    /// 429 asking the client to wait this many seconds
+    RateLimited(u64),
+    /// `Content` after a delay
+    Slow(Duration, String),
+    /// Never answers, leaving the client to time out
+    Hang,
+}
+
+impl Reply {
+    /// Well-formed decision JSON as the assistant message
+    pub fn decision(action: &str, reason: &str) -> Self {
+        Reply::Content(json!({ "action": action, "reason": reason }).to_string())
+    }
+}
+
+#[derive(Default)]
+struct Shared {
+    replies: Mutex<VecDeque<Reply>>,
+    requests: Mutex<Vec<Value>>,
+}
+
+/// Mock reviewer API listening on a random local port
+pub struct MockOpenAi {
+    addr: SocketAddr,
+    shared: Arc<Shared>,
+    server: JoinHandle<()>,
+}
+
+impl MockOpenAi {
+    /// Start serving, answering the n-th request with `replies[n]`
+    pub async fn start(replies: Vec<Reply>) -> Self {
+        let shared = Arc::new(Shared {
+            replies: Mutex::new(replies.into()),
+            ..Default::default()
+        });
+
+        let app = Router::new()
+            .route("/chat/completions", post(chat_completions))
+            .with_state(shared.clone());
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind mock server");
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let _ = axum::serve(listener, app).await;
+        });
+
+        Self {
+            addr,
+            shared,
+            server,
+        }
+    }
+
+    /// Base URL to give the reviewer client, without `/chat/completions`
+    pub fn base_url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Body of every request received, in order
+    pub fn requests(&self) -> Vec<Value> {
+        self.shared.requests.lock().unwrap().clone()
+    }
+
+    pub fn request_count(&self) -> usize {
+        self.shared.requests.lock().unwrap().len()
+    }
+}
+
+impl Drop for MockOpenAi {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+async fn chat_completions(State(shared): State<Arc<Shared>>, Json(body): Json<Value>) -> Response {
+    shared.requests.lock().unwrap().push(body);
+    let reply = shared.replies.lock().unwrap().pop_front();
+
+    match reply {
+        Some(Reply::Content(content)) => completion(&content),
+        Some(Reply::Raw(body)) => body.into_response(),
+        Some(Reply::Status(status)) => {
+            let status = StatusCode::from_u16(status).expect("valid status code");
+            (status, "scripted error").into_response()
+        }
+        Some(Reply::RateLimited(seconds)) => (
+            StatusCode::TOO_MANY_REQUESTS,
+            [(header::RETRY_AFTER, seconds.to_string())],
+            "slow down",
+        )
+            .into_response(),
+        Some(Reply::Slow(delay, content)) => {
+            tokio::time::sleep(delay).await;
+            completion(&content)
+        }
+        Some(Reply::Hang) => std::future::pending().await,
+        None => (StatusCode::INTERNAL_SERVER_ERROR, "script exhausted").into_response(),
+    }
+}
+
+fn completion(content: &str) -> Response {
+    Json(json!({
+        "id": "chatcmpl-mock",
+        "object": "chat.completion",
+        "choices": [{
+            "index": 0,
+            "message": { "role": "assistant", "content": content },
+            "finish_reason": "stop"
+        }]
+    }))
+    .into_response()
+}
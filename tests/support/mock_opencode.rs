@@ -0,0 +1,202 @@
+// Stand-in for `opencode serve`, for end-to-end tests that don't need the
+// real binary.
+//
+// Every prompt (the initial task or later guidance) releases the next
+// scripted turn of events onto the SSE stream.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::sse::{Event as SseEvent, KeepAlive, Sse},
+    routing::{get, post},
+    Json, Router,
+};
+use futures::stream::{self, Stream};
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+
+/// Id of the one session the mock hands out
+pub const MOCK_SESSION_ID: &str = "ses_mock";
+
+#[derive(Default)]
+struct Shared {
+    /// Turns not yet released, one per prompt
+    turns: Mutex<VecDeque<Vec<Value>>>,
+    /// Every event released so far, replayed to each new subscriber
+    events: Mutex<Vec<Value>>,
+    /// Woken whenever events are released
+    released: Notify,
+    prompts: Mutex<Vec<String>>,
+    sessions_created: Mutex<usize>,
+    aborts: Mutex<usize>,
+}
+
+/// Mock OpenCode server listening on a random local port
+pub struct MockOpenCode {
+    addr: SocketAddr,
+    shared: Arc<Shared>,
+    server: JoinHandle<()>,
+}
+
+impl MockOpenCode {
+    /// Start serving, releasing `turns[n]` on the n-th prompt
+    pub async fn start(turns: Vec<Vec<Value>>) -> Self {
+        let shared = Arc::new(Shared {
+            turns: Mutex::new(turns.into()),
+            ..Default::default()
+        });
+
+        let app = Router::new()
+            .route("/health", get(health))
+            .route("/global/health", get(health))
+            .route("/session", post(create_session))
+            .route("/session/:id/prompt_async", post(prompt_async))
+            .route("/session/:id/message", get(messages))
+            .route("/session/:id/abort", post(abort))
+            .route("/event", get(events))
+            .route("/global/event", get(global_events))
+            .with_state(shared.clone());
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind mock server");
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let _ = axum::serve(listener, app).await;
+        });
+
+        Self {
+            addr,
+            shared,
+            server,
+        }
+    }
+
+    pub fn base_url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    pub fn port(&self) -> u16 {
+        self.addr.port()
+    }
+
+    /// Text of every prompt received, in order
+    pub fn prompts(&self) -> Vec<String> {
+        self.shared.prompts.lock().unwrap().clone()
+    }
+
+    pub fn sessions_created(&self) -> usize {
+        *self.shared.sessions_created.lock().unwrap()
+    }
+
+    pub fn aborts(&self) -> usize {
+        *self.shared.aborts.lock().unwrap()
+    }
+}
+
+impl Drop for MockOpenCode {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+/// `message.part.updated` event carrying worker text
+pub fn text(text: &str) -> Value {
+    json!({
+        "type": "message.part.updated",
+        "properties": {
+            "sessionID": MOCK_SESSION_ID,
+            "part": { "type": "text", "text": text }
+        }
+    })
+}
+
+/// `session.idle` event, the worker finishing its turn
+pub fn idle() -> Value {
+    json!({
+        "type": "session.idle",
+        "properties": { "sessionID": MOCK_SESSION_ID }
+    })
+}
+
+async fn health() -> Json<Value> {
+    Json(json!({ "healthy": true, "version": "0.0.0-mock" }))
+}
+
+async fn create_session(State(shared): State<Arc<Shared>>) -> Json<Value> {
+    *shared.sessions_created.lock().unwrap() += 1;
+    Json(json!({
+        "id": MOCK_SESSION_ID,
+        "projectID": "prj_mock",
+        "directory": "/tmp",
+        "title": "Mock session",
+        "version": "0.0.0-mock",
+        "time": { "created": 0, "updated": 0 }
+    }))
+}
+
+async fn prompt_async(
+    State(shared): State<Arc<Shared>>,
+    Path(_id): Path<String>,
+    Json(body): Json<Value>,
+) -> StatusCode {
+    let text = body
+        .pointer("/parts/0/text")
+        .and_then(|v| v.as_str())
+        .unwrap_or_default()
+        .to_string();
+    shared.prompts.lock().unwrap().push(text);
+
+    let turn = shared.turns.lock().unwrap().pop_front().unwrap_or_default();
+    shared.events.lock().unwrap().extend(turn);
+    shared.released.notify_waiters();
+
+    StatusCode::NO_CONTENT
+}
+
+async fn messages(Path(_id): Path<String>) -> Json<Value> {
+    Json(json!([]))
+}
+
+async fn abort(State(shared): State<Arc<Shared>>, Path(_id): Path<String>) -> Json<Value> {
+    *shared.aborts.lock().unwrap() += 1;
+    Json(json!(true))
+}
+
+async fn events(
+    State(shared): State<Arc<Shared>>,
+) -> Sse<impl Stream<Item = Result<SseEvent, Infallible>>> {
+    Sse::new(event_stream(shared, |event| event)).keep_alive(KeepAlive::default())
+}
+
+async fn global_events(
+    State(shared): State<Arc<Shared>>,
+) -> Sse<impl Stream<Item = Result<SseEvent, Infallible>>> {
+    let wrap = |event: Value| json!({ "directory": "/tmp", "payload": event });
+    Sse::new(event_stream(shared, wrap)).keep_alive(KeepAlive::default())
+}
+
+/// Every released event from the start, then new ones as they arrive
+fn event_stream(
+    shared: Arc<Shared>,
+    wrap: fn(Value) -> Value,
+) -> impl Stream<Item = Result<SseEvent, Infallible>> {
+    stream::unfold((shared, 0), move |(shared, next)| async move {
+        loop {
+            // Registered before checking so a release in between isn't missed
+            let released = shared.released.notified();
+            let event = shared.events.lock().unwrap().get(next).cloned();
+            if let Some(event) = event {
+                drop(released);
+                let sse = SseEvent::default().data(wrap(event).to_string());
+                return Some((Ok(sse), (shared, next + 1)));
+            }
+            released.await;
+        }
+    })
+}
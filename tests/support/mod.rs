@@ -0,0 +1,7 @@
+// Shared helpers for tests that talk to mock servers
+//
+// Each test crate uses only some of them.
+#![allow(dead_code)]
+
+pub mod mock_openai;
+pub mod mock_opencode;
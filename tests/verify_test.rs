@@ -0,0 +1,132 @@
+use opencode_runner::verify::{failure_message, trim_output};
+use opencode_runner::VerificationResult;
+#[cfg(unix)]
+use opencode_runner::{verify::all_passed, Verifier};
+#[cfg(unix)]
+use std::time::{Duration, Instant};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Verifier for `commands` in a fresh directory; commands run through
+    /// `sh`, so this and the tests using it are unix-only
+    #[cfg(unix)]
+    fn verifier(commands: &[&str]) -> (tempfile::TempDir, Verifier) {
+        let dir = tempfile::tempdir().unwrap();
+        let commands = commands.iter().map(|c| c.to_string()).collect();
+        let verifier = Verifier::new(dir.path().to_path_buf(), commands);
+        (dir, verifier)
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_all_commands_pass() {
+        let (_dir, verifier) = verifier(&["true", "echo ok"]);
+        let results = verifier.run().await;
+
+        assert_eq!(results.len(), 2);
+        assert!(all_passed(&results));
+        assert_eq!(results[1].output, "ok");
+        assert_eq!(results[1].exit_code, Some(0));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_stops_at_first_failure() {
+        let (_dir, verifier) = verifier(&["echo broken >&2; exit 3", "echo unreachable"]);
+        let results = verifier.run().await;
+
+        assert_eq!(results.len(), 1);
+        assert!(!all_passed(&results));
+        assert_eq!(results[0].exit_code, Some(3));
+        assert_eq!(results[0].output, "broken");
+        assert_eq!(
+            results[0].summary(),
+            "`echo broken >&2; exit 3` failed (exit 3)"
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_runs_in_working_dir() {
+        let (dir, verifier) = verifier(&["test -f marker"]);
+        assert!(!all_passed(&verifier.run().await));
+
+        std::fs::write(dir.path().join("marker"), "").unwrap();
+        assert!(all_passed(&verifier.run().await));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_timeout_fails_verification() {
+        let (_dir, verifier) = verifier(&["sleep 10", "echo unreachable"]);
+        let verifier = verifier.with_timeout(Duration::from_millis(200));
+
+        let start = Instant::now();
+        let results = verifier.run().await;
+
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert_eq!(results.len(), 1);
+        assert!(!all_passed(&results));
+        assert_eq!(results[0].exit_code, None);
+        assert_eq!(results[0].output, "Timed out after 200ms");
+        assert_eq!(results[0].summary(), "`sleep 10` failed");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_timeout_kills_whole_command() {
+        // The subshell outlives `sh` unless its process group is killed
+        let (dir, verifier) = verifier(&["(sleep 1; touch finished); echo x"]);
+        let verifier = verifier.with_timeout(Duration::from_millis(200));
+
+        let results = verifier.run().await;
+        assert_eq!(results[0].output, "Timed out after 200ms");
+
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+        assert!(!dir.path().join("finished").exists());
+    }
+
+    #[test]
+    fn test_trim_output_keeps_tail() {
+        let output: String = (1..=100).map(|i| format!("line {}\n", i)).collect();
+        let trimmed = trim_output(&output);
+
+        assert!(trimmed.starts_with("... (output truncated)\n"));
+        assert!(trimmed.ends_with("line 100"));
+        assert!(!trimmed.contains("line 40\n"));
+        assert!(trimmed.contains("line 41\n"));
+
+        assert_eq!(trim_output("  short\n"), "short");
+        let long_line = "é".repeat(5000);
+        assert!(trim_output(&long_line).len() < 4100);
+    }
+
+    #[test]
+    fn test_failure_message_lists_failures() {
+        let results = vec![
+            VerificationResult {
+                command: "cargo build".to_string(),
+                success: true,
+                exit_code: Some(0),
+                output: String::new(),
+                duration_ms: 10,
+            },
+            VerificationResult {
+                command: "cargo test".to_string(),
+                success: false,
+                exit_code: Some(101),
+                output: "test it_works ... FAILED".to_string(),
+                duration_ms: 20,
+            },
+        ];
+
+        let message = failure_message(&results);
+        assert!(message.starts_with("The task is not done yet"));
+        assert!(
+            message.contains("`cargo test` failed (exit 101):\n```\ntest it_works ... FAILED\n```")
+        );
+        assert!(!message.contains("cargo build"));
+    }
+}